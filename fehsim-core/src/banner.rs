@@ -0,0 +1,443 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{Color, Pool};
+
+/// How the 5* rate increase is undone when a 5* unit is summoned.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter, serde::Serialize, serde::Deserialize)]
+pub enum PityReset {
+    /// Focus units reset the rate fully, off-focus units partially.
+    Partial,
+    /// Any 5* unit resets the rate fully.
+    Full,
+    /// Only focus units reset the rate, off-focus units do nothing.
+    FocusOnly,
+    /// The rate never resets.
+    Never,
+}
+
+impl fmt::Display for PityReset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use PityReset::*;
+        let s = match *self {
+            Partial => "Focus units reset fully, others partially",
+            Full => "Any 5* unit resets fully",
+            FocusOnly => "Only focus units reset",
+            Never => "Never resets",
+        };
+        f.write_str(s)
+    }
+}
+
+impl PityReset {
+    /// Gives the new pity count after a circle that gave the listed units.
+    /// `pity_count` already includes the summons made during the circle, and
+    /// `partial_reset` is the number of summons that each off-focus 5* takes
+    /// away when the reset is partial.
+    pub fn apply(
+        self,
+        pity_count: u32,
+        partial_reset: u32,
+        got_focus: bool,
+        nonfocus_count: u32,
+    ) -> u32 {
+        match self {
+            PityReset::Partial => {
+                if got_focus {
+                    0
+                } else {
+                    pity_count.saturating_sub(partial_reset * nonfocus_count)
+                }
+            }
+            PityReset::Full => {
+                if got_focus || nonfocus_count > 0 {
+                    0
+                } else {
+                    pity_count
+                }
+            }
+            PityReset::FocusOnly => {
+                if got_focus {
+                    0
+                } else {
+                    pity_count
+                }
+            }
+            PityReset::Never => pity_count,
+        }
+    }
+}
+
+/// The number of units of each color in the summoning pools outside of the
+/// focus, in the order red, blue, green, colorless.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PoolSizes {
+    pub fivestar: [u8; 4],
+    /// How many of the 5* pool's units are only ever summoned at 5*, as
+    /// opposed to ones that can also be summoned at a lower rarity.
+    pub fivestar_exclusive: [u8; 4],
+    /// The units that can appear at the 4* special rate.
+    pub fourstar_special: [u8; 4],
+    pub fourstar: [u8; 4],
+    pub threestar: [u8; 4],
+}
+
+impl Default for PoolSizes {
+    fn default() -> Self {
+        PoolSizes {
+            fivestar: [18, 17, 12, 13],
+            fivestar_exclusive: [18, 17, 12, 13],
+            // The special rate pool is made up of units that were moved out
+            // of the 5* pool, so its color breakdown is assumed to match.
+            fourstar_special: [18, 17, 12, 13],
+            fourstar: [49, 50, 39, 53],
+            threestar: [49, 50, 39, 53],
+        }
+    }
+}
+
+/// The usual kinds of banners, each with its own rates and focus units.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter)]
+pub enum BannerType {
+    Normal,
+    NewHeroes,
+    Legendary,
+    HeroFest,
+    WeeklyRevival,
+    DoubleSpecial,
+    LegendaryRemix,
+}
+
+impl fmt::Display for BannerType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use BannerType::*;
+        let s = match *self {
+            Normal => "Normal (3%/3%)",
+            NewHeroes => "New Heroes with a 4* focus (3%/3%)",
+            Legendary => "Legendary/Mythic (8%/0%)",
+            HeroFest => "Hero Fest (5%/3%)",
+            WeeklyRevival => "Weekly Revival (4%/2%)",
+            DoubleSpecial => "Double Special Heroes (6%/0%)",
+            LegendaryRemix => "Legendary Remix (8%/0%)",
+        };
+        f.write_str(s)
+    }
+}
+
+impl TryFrom<u8> for BannerType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        for variant in BannerType::iter() {
+            if variant as usize == value as usize {
+                return Ok(variant);
+            }
+        }
+        Err(())
+    }
+}
+
+impl BannerType {
+    /// Changes the banner's rates, focus units, spark, and pools to match this
+    /// kind of banner. Everything else, like tickets or summons already made,
+    /// is left alone.
+    pub fn apply(self, banner: &mut Banner) {
+        use BannerType::*;
+        let (starting_rates, focus_sizes, fourstar_focus_sizes, spark) = match self {
+            Normal => ((30, 30), [1, 1, 1, 1], [0, 0, 0, 0], false),
+            NewHeroes => ((30, 30), [1, 1, 1, 1], [1, 0, 0, 0], true),
+            Legendary => ((80, 0), [3, 3, 3, 3], [0, 0, 0, 0], true),
+            HeroFest => ((50, 30), [1, 1, 1, 1], [0, 0, 0, 0], true),
+            WeeklyRevival => ((40, 20), [1, 1, 1, 1], [0, 0, 0, 0], false),
+            DoubleSpecial => ((60, 0), [2, 2, 2, 2], [0, 0, 0, 0], true),
+            // Two legendary or mythic heroes of each color, which split the
+            // color's share of the focus rate evenly.
+            LegendaryRemix => ((80, 0), [2, 2, 2, 2], [0, 0, 0, 0], true),
+        };
+        banner.starting_rates = starting_rates;
+        banner.lower_rates = Banner::usual_lower_rates(starting_rates);
+        for color in Color::iter() {
+            banner.set_focus_size(color, focus_sizes[color as usize]);
+        }
+        banner.fourstar_focus_sizes = fourstar_focus_sizes;
+        banner.spark = spark;
+        banner.focus_charges = matches!(self, Normal | NewHeroes);
+        banner.fourstar_special = matches!(self, NewHeroes | HeroFest);
+    }
+
+    /// Looks up a kind of banner by its name in code, like "HeroFest",
+    /// ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        BannerType::iter()
+            .find(|banner_type| format!("{:?}", banner_type).eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The kind of banner that the given one is set up as, if it's exactly
+    /// one of them.
+    pub fn of(banner: &Banner) -> Option<Self> {
+        BannerType::iter().find(|banner_type| {
+            let mut typed = banner.clone();
+            banner_type.apply(&mut typed);
+            typed == *banner
+        })
+    }
+}
+
+/// Representation of a summoning focus.
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Banner {
+    /// What the banner is called, if it's been given a name.
+    pub name: Option<String>,
+    pub focus_sizes: [i8; 4],
+    /// The names of the focus units of each color, where they've been
+    /// entered. Goal targets of a color go with the names in order.
+    pub focus_names: [Vec<String>; 4],
+    /// The focus and off-focus 5* rates before any increases, in tenths of a
+    /// percent.
+    pub starting_rates: (u16, u16),
+    /// The regular 4* and 3* rates before any increases, in tenths of a
+    /// percent. They're scaled to fill whatever the 5* rates leave, so only
+    /// their proportions matter.
+    pub lower_rates: (u16, u16),
+    pub focus_charges: bool,
+    pub fourstar_focus_sizes: [i8; 4],
+    pub fourstar_focus_rate: u8,
+    pub fourstar_special: bool,
+    pub spark: bool,
+    /// Whether the first summon on the banner is free.
+    pub free_first_summon: bool,
+    /// The number of summoning tickets available to use on the banner. Each
+    /// one makes the first summon of a circle free.
+    pub tickets: u16,
+    /// Whether the banner gives a free summon every day.
+    pub daily_free_summon: bool,
+    /// How many days the banner lasts.
+    pub duration_days: u16,
+    /// The number of summons already made without a 5* unit, for picking up
+    /// partway through a session.
+    pub starting_pity: u16,
+    /// The number of summons already made towards the next spark.
+    pub starting_spark: u16,
+    /// How much the 5* rate increases each time, in tenths of a percent.
+    pub pity_step: u8,
+    /// How many summons without a 5* it takes for the rate to increase.
+    pub pity_interval: u8,
+    /// The number of summons without a focus unit after which the next one
+    /// is guaranteed to be a focus unit, if the banner has such a guarantee.
+    /// The guarantee goes to the next stone opened of a color that has focus
+    /// units, so stones of other colors opened in the meantime don't use it up.
+    pub hard_pity: Option<u16>,
+    /// How the 5* rate increase is undone when a 5* unit is summoned.
+    pub pity_reset: PityReset,
+    /// The number of units in each of the pools outside of the focus.
+    pub pool_sizes: PoolSizes,
+}
+
+impl Default for Banner {
+    fn default() -> Self {
+        Banner {
+            name: None,
+            focus_sizes: [1, 1, 1, 1],
+            focus_names: Default::default(),
+            starting_rates: (30, 30),
+            lower_rates: (580, 360),
+            focus_charges: true,
+            fourstar_focus_sizes: [0, 0, 0, 0],
+            fourstar_focus_rate: 3,
+            fourstar_special: false,
+            spark: false,
+            free_first_summon: false,
+            tickets: 0,
+            daily_free_summon: false,
+            duration_days: 14,
+            starting_pity: 0,
+            starting_spark: 0,
+            pity_step: 5,
+            pity_interval: 5,
+            hard_pity: None,
+            pity_reset: PityReset::Partial,
+            pool_sizes: PoolSizes::default(),
+        }
+    }
+}
+
+impl Banner {
+    /// The 4* and 3* rates that banners with the given 5* rates usually have.
+    pub fn usual_lower_rates(starting_rates: (u16, u16)) -> (u16, u16) {
+        if starting_rates == (60, 0) {
+            // The lower-rarity breakdown on double special heroes banners is
+            // different for no apparent reason.
+            (600, 340)
+        } else {
+            (580, 360)
+        }
+    }
+
+    /// The name of a focus unit, counting from 0 among the units of its color,
+    /// if it's been entered.
+    pub fn focus_name(&self, color: Color, index: usize) -> Option<&str> {
+        self.focus_names[color as usize]
+            .get(index)
+            .map(String::as_str)
+    }
+
+    /// Names the focus units of a color. Unless the list is empty, the number
+    /// of focus units of the color becomes the number of names.
+    pub fn set_focus_names(&mut self, color: Color, names: Vec<String>) {
+        if !names.is_empty() {
+            self.focus_sizes[color as usize] = names.len().min(i8::MAX as usize) as i8;
+        }
+        self.focus_names[color as usize] = names;
+    }
+
+    /// Changes the number of focus units of a color, forgetting the names of
+    /// any units that are taken away. Negative sizes mean that none has been
+    /// entered yet.
+    pub fn set_focus_size(&mut self, color: Color, size: i8) {
+        self.focus_sizes[color as usize] = size;
+        self.focus_names[color as usize].truncate(size.max(0) as usize);
+    }
+
+    /// A copy of the banner without the settings that can't change the
+    /// results, like its name, so that banners that only differ in those
+    /// compare equal.
+    pub fn results_settings(&self) -> Banner {
+        Banner {
+            name: None,
+            focus_names: Default::default(),
+            duration_days: if self.daily_free_summon {
+                self.duration_days
+            } else {
+                0
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Describes anything that makes the banner impossible to summon on, so
+    /// that the reason can be shown instead of just not running.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.focus_sizes.iter().all(|&size| size <= 0) {
+            problems.push("The banner needs at least one 5* focus unit.".into());
+        }
+        // Something has to be left over for the 4* and 3* units that the rate
+        // increases come out of.
+        if self.starting_rates.0 + self.starting_rates.1 >= 1000 {
+            problems.push("The 5* rates add up to 100% or more.".into());
+        }
+        problems
+    }
+
+    /// The number of rate increases after which the 5* rate goes all the way
+    /// up to 100%. That happens once the increases would add up to 12.5%,
+    /// which is after 25 of the usual 0.5% increases. Banners whose rate
+    /// doesn't increase never get there, and only have the first tier.
+    pub fn max_pity_increases(&self) -> u32 {
+        match self.pity_step as u32 {
+            0 => 0,
+            step => (125 + step - 1) / step,
+        }
+    }
+
+    /// The number of summons that each off-focus 5* unit takes off the pity
+    /// count when the rates reset partially. It lowers the rate by 2%, the
+    /// same as four of the usual 0.5% increases.
+    pub fn partial_reset_summons(&self) -> u32 {
+        match self.pity_step as u32 {
+            0 => 0,
+            step => (20 + step - 1) / step * self.pity_interval.max(1) as u32,
+        }
+    }
+
+    /// The chance of summoning from each pool before any rate increases, in
+    /// percent, indexed by `Pool`.
+    pub fn base_rates(&self) -> [f32; 6] {
+        let (focus, fivestar) = self.starting_rates;
+        let focus = focus as f32 / 10.0;
+        let fivestar = fivestar as f32 / 10.0;
+        // Rates with nothing in them can't be scaled up to fill the rest.
+        let (fourstar, threestar) = match self.lower_rates {
+            (0, 0) => Banner::usual_lower_rates(self.starting_rates),
+            rates => rates,
+        };
+        let lower_total = (fourstar + threestar) as f32;
+        let rest = 100.0 - focus - fivestar;
+        let fourstar = rest * fourstar as f32 / lower_total;
+        let threestar = rest * threestar as f32 / lower_total;
+        let mut bases = [focus, fivestar, 0.0, 0.0, fourstar, threestar];
+        if self.has_fourstar_focus() {
+            // The 4* focus rate comes out of the regular 4* rate.
+            let fourstar_focus = self.fourstar_focus_rate as f32;
+            bases[Pool::FourstarFocus as usize] = fourstar_focus;
+            bases[Pool::Fourstar as usize] -= fourstar_focus;
+        }
+        if self.fourstar_special {
+            // So does the special rate.
+            bases[Pool::FourstarSpecial as usize] = 3.0;
+            bases[Pool::Fourstar as usize] -= 3.0;
+        }
+        // A custom 4* rate can be too small for both of those to come out of.
+        bases[Pool::Fourstar as usize] = bases[Pool::Fourstar as usize].max(0.0);
+        bases
+    }
+
+    /// Whether focus charges can have any effect on this banner.
+    pub fn has_focus_charges(&self) -> bool {
+        self.focus_charges && self.starting_rates.1 > 0
+    }
+
+    /// Whether the banner has any 4* focus units.
+    pub fn has_fourstar_focus(&self) -> bool {
+        self.fourstar_focus_sizes.iter().any(|&x| x > 0)
+    }
+
+    /// The number of units of the given color that can be summoned from the
+    /// given pool on this banner.
+    pub fn pool_size(&self, pool: Pool, color: Color) -> usize {
+        let color = color as usize;
+        let size = match pool {
+            Pool::Focus => self.focus_sizes[color].max(0) as u8,
+            Pool::Fivestar if self.starting_rates.1 == 0 => 0,
+            Pool::Fivestar => self.pool_sizes.fivestar[color],
+            Pool::FourstarFocus => self.fourstar_focus_sizes[color].max(0) as u8,
+            Pool::FourstarSpecial if !self.fourstar_special => 0,
+            Pool::FourstarSpecial => self.pool_sizes.fourstar_special[color],
+            // The 4* rate can be used up by the 4* focus and special rates,
+            // and either rate can be set to nothing.
+            Pool::Fourstar | Pool::Threestar if self.base_rates()[pool as usize] <= 0.0 => 0,
+            Pool::Fourstar => self.pool_sizes.fourstar[color],
+            Pool::Threestar => self.pool_sizes.threestar[color],
+        };
+        size as usize
+    }
+
+    /// The chance, in percent, that a summon is one specific unit of the
+    /// given pool, before any rate increases or focus charges. Only known for
+    /// the pools with a rate of their own.
+    pub fn unit_chance(&self, pool: Pool, color: Color) -> Option<f32> {
+        let rate = match pool {
+            Pool::Focus => self.starting_rates.0 as f32 / 10.0,
+            Pool::Fivestar => self.starting_rates.1 as f32 / 10.0,
+            Pool::FourstarFocus => self.fourstar_focus_rate as f32,
+            Pool::FourstarSpecial => 3.0,
+            Pool::Fourstar | Pool::Threestar => return None,
+        };
+        // Colors are picked in proportion to how many units they have in the
+        // pool, so every unit of the pool is as likely as any other.
+        let total: usize = Color::iter().map(|color| self.pool_size(pool, color)).sum();
+        if self.pool_size(pool, color) == 0 || total == 0 {
+            return None;
+        }
+        Some(rate / total as f32)
+    }
+
+    /// Parses data from the representation used in query strings to share settings.
+    pub fn from_query_string(s: &str) -> Option<Self> {
+        let data = base64::decode(s).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+}
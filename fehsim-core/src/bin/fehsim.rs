@@ -0,0 +1,239 @@
+//! Command line interface for running simulations outside of the browser.
+//!
+//! ```text
+//! fehsim --goal RedFocus --copies 2 --focus 2,1,1,1 --spark --runs 100000
+//! fehsim --config settings.json --json
+//! ```
+
+use std::env;
+use std::fs;
+use std::process;
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use fehsim_core::banner::{Banner, BannerType};
+use fehsim_core::counter::Counter;
+use fehsim_core::goal::{Goal, GoalPreset};
+use fehsim_core::sim::{self, Sim};
+use fehsim_core::stats;
+use fehsim_core::strategy::Strategy;
+
+const USAGE: &str = "\
+Usage: fehsim [options]
+
+Settings:
+  --config FILE       Read the banner, goal, and strategy from a JSON file
+  --goal PRESET       Goal preset, like AnyFocus or RedFocus
+  --copies N          Number of copies for the goal preset
+  --type TYPE         Banner type, like LegendaryRemix or HeroFest, which sets
+                      the rates, focus counts, and spark for later options to
+                      change
+  --rates F,O         Starting focus and off-focus 5* rates, like 4.5,1.5
+  --focus R,B,G,C     Number of focus units of each color
+  --spark             Banner has a spark
+  --no-focus-charges  Banner doesn't have focus charges
+  --fourstar-special  Banner has the 4* special rate
+
+Running:
+  --runs N            Number of runs to simulate (default 10000)
+  --seed N            Seed for the random number generator
+
+Output:
+  --json              Print the results as JSON
+  --dump-config       Print the settings as JSON, for editing and using with --config
+";
+
+/// The percentiles shown in the results.
+const PERCENTILES: [f32; 6] = [0.25, 0.5, 0.75, 0.9, 0.95, 0.99];
+
+/// Everything that decides how the simulation runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Config {
+    banner: Banner,
+    goal: Goal,
+    strategy: Strategy,
+}
+
+/// Options that control the program itself rather than the simulation.
+struct Options {
+    runs: u32,
+    seed: Option<u64>,
+    json: bool,
+    dump_config: bool,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (config, options) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if options.dump_config {
+        println!("{}", serde_json::to_string_pretty(&config).unwrap());
+        return;
+    }
+    let problems = config.banner.problems();
+    if !problems.is_empty() {
+        eprintln!("{}", problems.join("\n"));
+        process::exit(1);
+    }
+    if !config.goal.is_available(&config.banner) {
+        eprintln!("The goal isn't possible on this banner.");
+        process::exit(1);
+    }
+    if let Err(message) = config.strategy.rules() {
+        eprintln!("Invalid strategy script: {}", message);
+        process::exit(1);
+    }
+
+    let seed = options.seed.unwrap_or_else(Sim::random_seed);
+    let mut sim = sim::new_system(config.banner, config.goal, config.strategy, seed);
+    let mut data = Counter::default();
+    let mut unfinished = 0;
+    for _ in 0..options.runs {
+        let result = sim.roll_until_goal();
+        if result.reached_goal {
+            data[result.orbs] += 1;
+        } else {
+            unfinished += 1;
+        }
+    }
+    if data.is_empty() {
+        eprintln!("None of the runs reached the goal.");
+        process::exit(1);
+    }
+
+    let percentiles = stats::percentiles(&data, &PERCENTILES);
+    if options.json {
+        let percentiles: serde_json::Map<String, serde_json::Value> = PERCENTILES
+            .iter()
+            .zip(percentiles.iter())
+            .map(|(pct, orbs)| ((pct * 100.0).round().to_string(), (*orbs).into()))
+            .collect();
+        let results = serde_json::json!({
+            "runs": options.runs,
+            "seed": seed,
+            "unfinished": unfinished,
+            "average": stats::mean(&data),
+            "percentiles": percentiles,
+        });
+        println!("{}", serde_json::to_string_pretty(&results).unwrap());
+    } else {
+        println!("Runs: {}, seed: {}", options.runs, seed);
+        if unfinished > 0 {
+            println!(
+                "Runs that stopped without reaching the goal, left out below: {}",
+                unfinished
+            );
+        }
+        println!("Average: {:.1} orbs", stats::mean(&data));
+        println!();
+        println!("Percentile  Orbs");
+        for (pct, orbs) in PERCENTILES.iter().zip(percentiles.iter()) {
+            println!("{:>9}%  {:>4}", (pct * 100.0).round(), orbs);
+        }
+    }
+}
+
+/// Reads the settings and options from the command line arguments.
+fn parse_args(args: &[String]) -> Result<(Config, Options), String> {
+    let mut config = Config::default();
+    let mut options = Options {
+        runs: 10000,
+        seed: None,
+        json: false,
+        dump_config: false,
+    };
+    let mut preset = None;
+    let mut copies = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "--config" => {
+                let path = value()?;
+                let text = fs::read_to_string(path)
+                    .map_err(|err| format!("Couldn't read {}: {}", path, err))?;
+                config = serde_json::from_str(&text)
+                    .map_err(|err| format!("Couldn't parse {}: {}", path, err))?;
+            }
+            "--goal" => {
+                let name = value()?;
+                preset = Some(
+                    GoalPreset::iter()
+                        .find(|preset| format!("{:?}", preset).eq_ignore_ascii_case(name))
+                        .ok_or_else(|| format!("Unknown goal preset \"{}\"", name))?,
+                );
+            }
+            "--type" => {
+                let name = value()?;
+                BannerType::from_name(name)
+                    .ok_or_else(|| format!("Unknown banner type \"{}\"", name))?
+                    .apply(&mut config.banner);
+            }
+            "--copies" => copies = parse_number(arg, value()?)?,
+            "--rates" => {
+                let rates: Vec<f32> = parse_list(arg, value()?)?;
+                match &*rates {
+                    &[focus, fivestar] => {
+                        let tenths = |rate: f32| (rate * 10.0).round().clamp(0.0, 1000.0) as u16;
+                        config.banner.starting_rates = (tenths(focus), tenths(fivestar))
+                    }
+                    _ => return Err(format!("{} needs two rates", arg)),
+                }
+            }
+            "--focus" => {
+                let sizes: Vec<i8> = parse_list(arg, value()?)?;
+                match &*sizes {
+                    &[red, blue, green, colorless] => {
+                        config.banner.focus_sizes = [red, blue, green, colorless]
+                    }
+                    _ => return Err(format!("{} needs four counts", arg)),
+                }
+            }
+            "--spark" => config.banner.spark = true,
+            "--no-focus-charges" => config.banner.focus_charges = false,
+            "--fourstar-special" => config.banner.fourstar_special = true,
+            "--runs" => options.runs = parse_number(arg, value()?)?,
+            "--seed" => options.seed = Some(parse_number(arg, value()?)?),
+            "--json" => options.json = true,
+            "--dump-config" => options.dump_config = true,
+            "--help" | "-h" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("Unknown option \"{}\"", arg)),
+        }
+    }
+
+    if let Some(preset) = preset {
+        config.goal = Goal::Preset(preset, copies);
+    }
+    Ok((config, options))
+}
+
+/// Parses the value of an option that takes a number.
+fn parse_number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} needs a number, not \"{}\"", arg, value))
+}
+
+/// Parses the value of an option that takes a comma-separated list of numbers.
+fn parse_list<T: std::str::FromStr>(arg: &str, value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|part| parse_number(arg, part.trim()))
+        .collect()
+}
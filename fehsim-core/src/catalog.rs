@@ -0,0 +1,141 @@
+//! Banners listed in a feed, so that the ones running in the game can be
+//! picked instead of entered by hand. The feed is a JSON list of entries like:
+//!
+//! ```text
+//! {
+//!     "name": "Legendary Eitri",
+//!     "type": "Legendary",
+//!     "focus": [["Legendary Eitri"], [], [], []],
+//!     "start": "2025-01-31",
+//!     "end": "2025-02-13"
+//! }
+//! ```
+//!
+//! Only the name is required. The type is one of the `BannerType` names and
+//! fills in everything that the entry doesn't give, and the focus units are
+//! listed by color in the order red, blue, green, colorless. If any focus
+//! units are listed, colors without any have none; otherwise the type's focus
+//! sizes are kept. The start and end are the first and last days of the
+//! banner.
+
+use serde::Deserialize;
+
+use crate::banner::{Banner, BannerType};
+use crate::date::Date;
+use crate::Color;
+
+/// A banner listed in the feed.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CatalogEntry {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub banner_type: Option<String>,
+    /// The names of the focus units of each color.
+    #[serde(default)]
+    pub focus: [Vec<String>; 4],
+    /// The focus and off-focus 5* rates in percent, if they're different from
+    /// the type's.
+    #[serde(default)]
+    pub rates: Option<(f32, f32)>,
+    /// Whether the banner has a spark, if it's different from the type.
+    #[serde(default)]
+    pub spark: Option<bool>,
+    #[serde(default)]
+    pub start: Option<String>,
+    #[serde(default)]
+    pub end: Option<String>,
+}
+
+impl CatalogEntry {
+    /// The first day of the banner, if the feed gives a valid one.
+    pub fn start(&self) -> Option<Date> {
+        self.start.as_deref().and_then(Date::parse)
+    }
+
+    /// The last day of the banner, if the feed gives a valid one.
+    pub fn end(&self) -> Option<Date> {
+        self.end.as_deref().and_then(Date::parse)
+    }
+
+    /// The number of days that the banner has left as of `today`, counting
+    /// today, or all of its days if it hasn't started yet. Banners that have
+    /// ended have none left.
+    pub fn days_left(&self, today: Date) -> Option<i64> {
+        let end = self.end()?;
+        let first = match self.start() {
+            Some(start) if start > today => start,
+            _ => today,
+        };
+        Some((first.days_until(end) + 1).max(0))
+    }
+
+    /// The banner that the entry describes, as of `today`. The banner's
+    /// duration, which its daily free summons go by, is the days that it has
+    /// left.
+    pub fn banner(&self, today: Date) -> Banner {
+        let mut banner = Banner::default();
+        if let Some(banner_type) = self.banner_type.as_deref().and_then(BannerType::from_name) {
+            banner_type.apply(&mut banner);
+        }
+        banner.name = Some(self.name.clone());
+        if self.focus.iter().any(|names| !names.is_empty()) {
+            for (&color, names) in [Color::Red, Color::Blue, Color::Green, Color::Colorless]
+                .iter()
+                .zip(&self.focus)
+            {
+                banner.set_focus_size(color, 0);
+                banner.set_focus_names(color, names.clone());
+            }
+        }
+        if let Some((focus, fivestar)) = self.rates {
+            let tenths = |rate: f32| (rate * 10.0).round().clamp(0.0, 1000.0) as u16;
+            banner.starting_rates = (tenths(focus), tenths(fivestar));
+            banner.lower_rates = Banner::usual_lower_rates(banner.starting_rates);
+        }
+        if let Some(spark) = self.spark {
+            banner.spark = spark;
+        }
+        if let Some(days) = self.days_left(today) {
+            banner.duration_days = days.min(u16::MAX as i64) as u16;
+        }
+        banner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(banner_type: &str, focus: [&[&str]; 4]) -> CatalogEntry {
+        CatalogEntry {
+            name: banner_type.into(),
+            banner_type: Some(banner_type.into()),
+            focus: [
+                focus[0].iter().map(|&name| name.into()).collect(),
+                focus[1].iter().map(|&name| name.into()).collect(),
+                focus[2].iter().map(|&name| name.into()).collect(),
+                focus[3].iter().map(|&name| name.into()).collect(),
+            ],
+            rates: None,
+            spark: None,
+            start: None,
+            end: None,
+        }
+    }
+
+    #[test]
+    fn colors_without_names_have_no_focus_units() {
+        let today = Date::from_ymd(2025, 1, 1).unwrap();
+        let revival = entry("WeeklyRevival", [&["Ryoma"], &[], &[], &[]]);
+        assert_eq!(revival.banner(today).focus_sizes, [1, 0, 0, 0]);
+        let legendary = entry("Legendary", [&["A"], &["B"], &["C"], &[]]);
+        assert_eq!(legendary.banner(today).focus_sizes, [1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn type_sizes_are_kept_without_names() {
+        let today = Date::from_ymd(2025, 1, 1).unwrap();
+        let legendary = entry("Legendary", [&[], &[], &[], &[]]);
+        assert_eq!(legendary.banner(today).focus_sizes, [3, 3, 3, 3]);
+    }
+}
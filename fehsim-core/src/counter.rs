@@ -0,0 +1,210 @@
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Associative array of u32 -> u32 with the interface and implementation optimized
+/// for use as a counter for small numbers with a dense distribution.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(into = "Encoded")]
+pub struct Counter {
+    data: Vec<u32>,
+}
+
+/// The shortest stretch of equal counts that's saved as a repeat instead of
+/// one count at a time. Shorter ones take less room as separate counts.
+const MIN_REPEAT: u32 = 3;
+
+/// The form that counters are saved in, which stays small for the long, mostly
+/// empty tails that distributions of orbs tend to have. The counts are split
+/// into stretches of equal counts, and each stretch is saved as the difference
+/// from the count before it, which takes fewer digits than the count itself.
+#[derive(Serialize, Deserialize)]
+struct Encoded {
+    /// The difference between the count of each stretch and the one before
+    /// it, starting from 0.
+    deltas: Vec<i64>,
+    /// The index in `deltas` and the length of each stretch that covers more
+    /// than one count. The rest cover a single count.
+    repeats: Vec<(u32, u32)>,
+}
+
+/// Any of the forms that counters have been saved in. Before `Encoded`, the
+/// counts were saved one by one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Saved {
+    Encoded(Encoded),
+    Plain { data: Vec<u32> },
+}
+
+impl<'de> Deserialize<'de> for Counter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Only files and local storage, which are human-readable, can have
+        // counters saved in the older form. Telling the forms apart needs a
+        // self-describing format, which bincode isn't.
+        if deserializer.is_human_readable() {
+            Ok(match Saved::deserialize(deserializer)? {
+                Saved::Encoded(encoded) => encoded.into(),
+                Saved::Plain { data } => Counter { data },
+            })
+        } else {
+            Encoded::deserialize(deserializer).map(Counter::from)
+        }
+    }
+}
+
+impl From<Counter> for Encoded {
+    fn from(counter: Counter) -> Self {
+        let mut encoded = Encoded {
+            deltas: vec![],
+            repeats: vec![],
+        };
+        let mut previous = 0;
+        let mut counts = counter.data.iter().peekable();
+        while let Some(&count) = counts.next() {
+            let mut length = 1;
+            while counts.peek() == Some(&&count) {
+                counts.next();
+                length += 1;
+            }
+            encoded.deltas.push(count as i64 - previous as i64);
+            if length >= MIN_REPEAT {
+                encoded
+                    .repeats
+                    .push((encoded.deltas.len() as u32 - 1, length));
+            } else {
+                for _ in 1..length {
+                    encoded.deltas.push(0);
+                }
+            }
+            previous = count;
+        }
+        encoded
+    }
+}
+
+impl From<Encoded> for Counter {
+    fn from(encoded: Encoded) -> Self {
+        let mut data = vec![];
+        let mut repeats = encoded.repeats.iter().peekable();
+        let mut count: i64 = 0;
+        for (index, &delta) in encoded.deltas.iter().enumerate() {
+            count += delta;
+            let length = match repeats.peek() {
+                Some(&&(start, length)) if start as usize == index => {
+                    repeats.next();
+                    length
+                }
+                _ => 1,
+            };
+            let count = count.max(0).min(u32::MAX as i64) as u32;
+            data.extend((0..length).map(|_| count));
+        }
+        Counter { data }
+    }
+}
+
+impl Counter {
+    /// Adds all of the counts from another counter to this one.
+    pub fn merge(&mut self, other: &Counter) {
+        if other.data.len() > self.data.len() {
+            self.data.resize(other.data.len(), 0);
+        }
+        for (total, &count) in self.data.iter_mut().zip(other.data.iter()) {
+            *total += count;
+        }
+    }
+}
+
+impl Index<u32> for Counter {
+    type Output = u32;
+
+    /// Infallible. Returns 0 if index is out of range.
+    fn index(&self, index: u32) -> &Self::Output {
+        self.data.get(index as usize).unwrap_or(&0)
+    }
+}
+
+impl IndexMut<u32> for Counter {
+    /// Infallible. Resizes container if index is out of range.
+    fn index_mut(&mut self, index: u32) -> &mut Self::Output {
+        let index = index as usize;
+        if index >= self.data.len() {
+            self.data.resize(index + 1, 0);
+        }
+        &mut self.data[index]
+    }
+}
+
+impl Deref for Counter {
+    type Target = Vec<u32>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl DerefMut for Counter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(data: &[u32]) -> Counter {
+        Counter {
+            data: data.to_vec(),
+        }
+    }
+
+    /// Counters with stretches shorter than, as long as, and longer than
+    /// `MIN_REPEAT`, and ones that end in zeros.
+    fn examples() -> Vec<Counter> {
+        vec![
+            counter(&[]),
+            counter(&[0]),
+            counter(&[5]),
+            counter(&[0, 0, 3, 3, 1]),
+            counter(&[0, 0, 0, 7, 7, 7, 2]),
+            counter(&[0, 0, 0, 0, 0, 0, 4, 9, 9, 9, 9, 9, 1, 0]),
+            counter(&[8, 2, 0, 0]),
+            counter(&[8, 2, 0, 0, 0]),
+            counter(&[1, 0, 0, 0, 0, 0, 0, 0]),
+            counter(&[u32::MAX, 0, u32::MAX, u32::MAX, u32::MAX]),
+        ]
+    }
+
+    #[test]
+    fn round_trips_through_bincode() {
+        for counter in examples() {
+            let bytes = bincode::serialize(&counter).unwrap();
+            let decoded: Counter = bincode::deserialize(&bytes).unwrap();
+            assert_eq!(decoded, counter);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        for counter in examples() {
+            let text = serde_json::to_string(&counter).unwrap();
+            let decoded: Counter = serde_json::from_str(&text).unwrap();
+            assert_eq!(decoded, counter);
+        }
+    }
+
+    #[test]
+    fn repeats_are_saved_once() {
+        let encoded = Encoded::from(counter(&[0, 0, 0, 7, 7, 2, 2, 2, 2]));
+        assert_eq!(encoded.deltas, vec![0, 7, 0, -5]);
+        assert_eq!(encoded.repeats, vec![(0, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn reads_counts_saved_one_by_one() {
+        let decoded: Counter = serde_json::from_str(r#"{"data":[0,0,3,1]}"#).unwrap();
+        assert_eq!(decoded, counter(&[0, 0, 3, 1]));
+    }
+}
@@ -0,0 +1,137 @@
+//! Calendar dates, for knowing how long banners have left. Times of day and
+//! time zones are left to whatever gives the dates.
+
+use std::fmt;
+
+/// A day on the calendar, stored as the number of days since 1970-01-01.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    days: i64,
+}
+
+impl Date {
+    /// The given day of the given month, counting months and days from 1, if
+    /// there is such a day.
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return None;
+        }
+        // Counts from March so that leap days come at the end of the year.
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month_from_march = (month as i64 + 9) % 12;
+        let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Date {
+            days: era * 146_097 + day_of_era - 719_468,
+        })
+    }
+
+    /// Parses a date written like "2025-01-31".
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.trim().split('-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Date::from_ymd(year, month, day)
+    }
+
+    /// The year, month, and day of the date.
+    pub fn ymd(self) -> (i64, u32, u32) {
+        let days = self.days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+
+    /// The number of days from this date until the other one, which is
+    /// negative if the other one comes first.
+    pub fn days_until(self, other: Date) -> i64 {
+        other.days - self.days
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_count_from_1970() {
+        assert_eq!(Date::from_ymd(1970, 1, 1).unwrap().days, 0);
+        assert_eq!(Date::from_ymd(1969, 12, 31).unwrap().days, -1);
+        assert_eq!(Date::from_ymd(2000, 3, 1).unwrap().days, 11_017);
+    }
+
+    #[test]
+    fn dates_survive_a_round_trip() {
+        for days in (-800_000..800_000).step_by(97) {
+            let date = Date { days };
+            let (year, month, day) = date.ymd();
+            assert_eq!(Date::from_ymd(year, month, day), Some(date));
+            // Written years don't have signs.
+            if year >= 0 {
+                assert_eq!(Date::parse(&date.to_string()), Some(date));
+            }
+        }
+    }
+
+    #[test]
+    fn leap_days_only_exist_in_leap_years() {
+        assert!(Date::from_ymd(2024, 2, 29).is_some());
+        assert!(Date::from_ymd(2000, 2, 29).is_some());
+        assert!(Date::from_ymd(2023, 2, 29).is_none());
+        assert!(Date::from_ymd(1900, 2, 29).is_none());
+        let before = Date::from_ymd(2024, 2, 28).unwrap();
+        assert_eq!(before.days_until(Date::from_ymd(2024, 3, 1).unwrap()), 2);
+        let before = Date::from_ymd(2023, 2, 28).unwrap();
+        assert_eq!(before.days_until(Date::from_ymd(2023, 3, 1).unwrap()), 1);
+    }
+
+    #[test]
+    fn only_whole_dates_are_parsed() {
+        let date = Date::parse(" 2025-01-31 ").unwrap();
+        assert_eq!(date.ymd(), (2025, 1, 31));
+        assert_eq!(date.to_string(), "2025-01-31");
+        assert!(Date::parse("2025-04-31").is_none());
+        assert!(Date::parse("2025-13-01").is_none());
+        assert!(Date::parse("2025-00-10").is_none());
+        assert!(Date::parse("2025-01").is_none());
+        assert!(Date::parse("2025-01-31-1").is_none());
+        assert!(Date::parse("January 31").is_none());
+    }
+}
@@ -0,0 +1,509 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{banner::Banner, Color, Pool};
+
+pub mod expr;
+
+use expr::GoalExpr;
+
+/// Pre-set options for common goals.
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GoalPreset {
+    AnyFocus,
+    AllFocus,
+    RedFocus,
+    AnyRed,
+    RedFourstarFocus,
+    BlueFocus,
+    AnyBlue,
+    BlueFourstarFocus,
+    GreenFocus,
+    AnyGreen,
+    GreenFourstarFocus,
+    ColorlessFocus,
+    AnyColorless,
+    ColorlessFourstarFocus,
+    OneOfEachColor,
+    TotalFocus,
+    AnyExclusive,
+    TotalFivestar,
+}
+
+impl fmt::Display for GoalPreset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use crate::goal::GoalPreset::*;
+        let s = match *self {
+            AnyFocus => "Any 5* focus unit",
+            AllFocus => "Every 5* focus unit (complete the banner)",
+            RedFocus => "Specific red 5* focus unit",
+            RedFourstarFocus => "The red 4* focus unit",
+            AnyRed => "Any red 5* focus unit",
+            BlueFocus => "Specific blue 5* focus unit",
+            BlueFourstarFocus => "The blue 4* focus unit",
+            AnyBlue => "Any blue 5* focus unit",
+            GreenFocus => "Specific green 5* focus unit",
+            GreenFourstarFocus => "The green 4* focus unit",
+            AnyGreen => "Any green 5* focus unit",
+            ColorlessFocus => "Specific colorless 5* focus unit",
+            AnyColorless => "Any colorless 5* focus unit",
+            ColorlessFourstarFocus => "The colorless 4* focus unit",
+            OneOfEachColor => "A 5* focus unit of each color",
+            TotalFocus => "5* focus units of any color in total",
+            AnyExclusive => "Any 5*-exclusive unit, focus or not",
+            TotalFivestar => "5* units of any kind in total",
+        };
+        f.write_str(s)
+    }
+}
+
+impl TryFrom<u8> for GoalPreset {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        for variant in GoalPreset::iter() {
+            if variant as usize == value as usize {
+                return Ok(variant);
+            }
+        }
+        Err(())
+    }
+}
+
+impl GoalPreset {
+    /// Determines whether or not the selected preset is a goal that it is
+    /// possible to achieve on the banner.
+    pub fn is_available(self, banner: &Banner) -> bool {
+        use GoalPreset::*;
+        match self {
+            AnyFocus | AllFocus | OneOfEachColor | TotalFocus => {
+                banner.focus_sizes.iter().any(|&x| x > 0)
+            }
+            RedFocus | AnyRed => banner.focus_sizes[0] > 0,
+            BlueFocus | AnyBlue => banner.focus_sizes[1] > 0,
+            GreenFocus | AnyGreen => banner.focus_sizes[2] > 0,
+            ColorlessFocus | AnyColorless => banner.focus_sizes[3] > 0,
+            RedFourstarFocus => banner.fourstar_focus_sizes[0] > 0 && banner.focus_sizes[0] > 0,
+            BlueFourstarFocus => banner.fourstar_focus_sizes[1] > 0 && banner.focus_sizes[1] > 0,
+            GreenFourstarFocus => banner.fourstar_focus_sizes[2] > 0 && banner.focus_sizes[2] > 0,
+            ColorlessFourstarFocus => {
+                banner.fourstar_focus_sizes[3] > 0 && banner.focus_sizes[3] > 0
+            }
+            AnyExclusive => Color::iter().any(|color| {
+                exclusive_focus_count(banner, color) > 0
+                    || banner.starting_rates.1 > 0
+                        && banner.pool_sizes.fivestar_exclusive[color as usize] > 0
+            }),
+            TotalFivestar => Color::iter().any(|color| {
+                banner.pool_size(Pool::Focus, color) > 0
+                    || banner.pool_size(Pool::Fivestar, color) > 0
+            }),
+        }
+    }
+
+    /// Says whether or not the preset has only a single unit that counts for
+    /// completing the goal.
+    pub fn is_single_target(&self) -> bool {
+        use GoalPreset::*;
+        matches!(
+            self,
+            RedFocus
+                | BlueFocus
+                | GreenFocus
+                | ColorlessFocus
+                | RedFourstarFocus
+                | BlueFourstarFocus
+                | GreenFourstarFocus
+                | ColorlessFourstarFocus
+        )
+    }
+
+    /// Says whether or not the preset's count of copies has any effect.
+    pub fn uses_count(self) -> bool {
+        self.is_single_target()
+            || self == GoalPreset::TotalFocus
+            || self == GoalPreset::TotalFivestar
+    }
+}
+
+/// Whether the given goal is to achieve all of the goal parts or just a single one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GoalKind {
+    Any,
+    All,
+    /// All of the goal parts, without spending more than the given number of
+    /// orbs. Runs stop once the orbs run out, whether or not the goal was
+    /// reached.
+    Budget(u32),
+    /// Any one of the goal parts of each color that has goal parts.
+    EachColor,
+    /// The given number of copies in total, from any of the goal parts. Each
+    /// part still only counts for up to its own number of copies.
+    Total(u16),
+    /// At least the given number of the goal parts.
+    AtLeast(u8),
+    /// The goal parts combined by an expression like "1 and (2 or 3)". See
+    /// the `expr` module for how it's written.
+    Expression(String),
+}
+
+/// A single unit that the goal is trying to obtain.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct GoalPart {
+    pub unit_color: Color,
+    pub num_copies: u16,
+    /// Which pool the unit is summoned from. 4* focus units are also
+    /// counted when they show up as 5* focus units, and units outside of the
+    /// focus are specific units among the pool's units of their color.
+    pub pool: Pool,
+}
+
+/// The flexible representation of a goal
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomGoal {
+    pub kind: GoalKind,
+    pub goals: Vec<GoalPart>,
+}
+
+impl CustomGoal {
+    /// Parses an expression that combines this goal's parts, or describes
+    /// the first problem with it.
+    pub fn expression(&self, text: &str) -> Result<GoalExpr, String> {
+        expr::parse(text, self.goals.len())
+    }
+
+    /// Merges parts that have to be the same unit into a single part with
+    /// all of their copies. Parts are different units unless the banner
+    /// doesn't have enough units of their color in their pool to go around.
+    /// Only goals that need all of their parts are merged, since that's when
+    /// the copies add up.
+    pub fn merge_duplicates(&mut self, banner: &Banner) {
+        match self.kind {
+            GoalKind::All | GoalKind::Budget(_) => {}
+            _ => return,
+        }
+        let mut index = 0;
+        while index < self.goals.len() {
+            if self.is_part_possible(index, banner) {
+                index += 1;
+                continue;
+            }
+            let part = self.goals[index];
+            // Banners without any of the units have nothing to merge into,
+            // so the goal is left impossible instead.
+            let same_unit = self.goals[..index]
+                .iter_mut()
+                .rev()
+                .find(|other| other.pool == part.pool && other.unit_color == part.unit_color);
+            match same_unit {
+                Some(other) => {
+                    other.num_copies = other.num_copies.saturating_add(part.num_copies);
+                    self.goals.remove(index);
+                }
+                None => index += 1,
+            }
+        }
+    }
+
+    /// Which of the units of its color and pool the part at the given index
+    /// is, counting from 0. Each part is a different unit from the earlier
+    /// ones.
+    pub fn unit_index(&self, index: usize) -> usize {
+        let part = &self.goals[index];
+        self.goals[..index]
+            .iter()
+            .filter(|other| other.pool == part.pool && other.unit_color == part.unit_color)
+            .count()
+    }
+
+    /// Whether the part at the given index can be finished on the banner.
+    /// Like with all of the parts, only as many parts of a color as there are
+    /// units to go around can be finished.
+    fn is_part_possible(&self, index: usize, banner: &Banner) -> bool {
+        let part = &self.goals[index];
+        self.unit_index(index) < banner.pool_size(part.pool, part.unit_color)
+    }
+}
+
+/// The number of 5* focus units of a color that are only ever summoned at 5*.
+/// The 4* focus units are the only focus units that aren't.
+fn exclusive_focus_count(banner: &Banner, color: Color) -> usize {
+    let color = color as usize;
+    (banner.focus_sizes[color].max(0) - banner.fourstar_focus_sizes[color].max(0)).max(0) as usize
+}
+
+/// What the units of a pool are called, for messages about them.
+fn pool_name(pool: Pool) -> &'static str {
+    match pool {
+        Pool::Focus => "focus units",
+        Pool::Fivestar => "off-focus 5* units",
+        Pool::FourstarFocus => "4* focus units",
+        Pool::FourstarSpecial => "4* special rate units",
+        Pool::Fourstar => "4* units",
+        Pool::Threestar => "3* units",
+    }
+}
+
+/// The goal of a summoning session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Goal {
+    Custom(CustomGoal),
+    Preset(GoalPreset, u16),
+}
+
+impl Default for Goal {
+    fn default() -> Self {
+        Goal::Preset(GoalPreset::AnyFocus, 1)
+    }
+}
+
+impl Goal {
+    /// Convert the current preset into a custom goal or retreive the current
+    /// custom goal.
+    pub fn as_custom(&self, banner: &Banner) -> CustomGoal {
+        use crate::goal::GoalKind::*;
+        use crate::goal::GoalPreset::*;
+        use crate::Color::*;
+
+        let (preset, count) = match self {
+            Goal::Preset(preset, count) => (*preset, *count),
+            Goal::Custom(custom) => return custom.clone(),
+        };
+
+        let count = if preset.uses_count() { count.max(1) } else { 1 };
+
+        let kind = match preset {
+            AllFocus => All,
+            OneOfEachColor => EachColor,
+            TotalFocus | TotalFivestar => Total(count),
+            // Every other preset is either Any* or has only one target
+            _ => Any,
+        };
+        let mut custom_goal = CustomGoal {
+            kind,
+            goals: vec![],
+        };
+
+        let mut add_part = |color: Color, pool: Pool| {
+            custom_goal.goals.push(GoalPart {
+                unit_color: color,
+                num_copies: count,
+                pool,
+            });
+        };
+        // Add an individual GoalPart for each unit that matches the
+        // conditions of the overall goal.
+        match preset {
+            AllFocus | AnyFocus | OneOfEachColor | TotalFocus => {
+                for idx in 0..banner.focus_sizes.len() {
+                    for _ in 0..banner.focus_sizes[idx] {
+                        add_part(Color::try_from(idx as u8).unwrap(), Pool::Focus);
+                    }
+                }
+            }
+            RedFocus => add_part(Red, Pool::Focus),
+            BlueFocus => add_part(Blue, Pool::Focus),
+            GreenFocus => add_part(Green, Pool::Focus),
+            ColorlessFocus => add_part(Colorless, Pool::Focus),
+            AnyRed => {
+                for _ in 0..banner.focus_sizes[0] {
+                    add_part(Red, Pool::Focus)
+                }
+            }
+            AnyBlue => {
+                for _ in 0..banner.focus_sizes[1] {
+                    add_part(Blue, Pool::Focus)
+                }
+            }
+            AnyGreen => {
+                for _ in 0..banner.focus_sizes[2] {
+                    add_part(Green, Pool::Focus)
+                }
+            }
+            AnyColorless => {
+                for _ in 0..banner.focus_sizes[3] {
+                    add_part(Colorless, Pool::Focus)
+                }
+            }
+            AnyExclusive => {
+                for color in Color::iter() {
+                    for _ in 0..exclusive_focus_count(banner, color) {
+                        add_part(color, Pool::Focus);
+                    }
+                    if banner.starting_rates.1 > 0 {
+                        for _ in 0..banner.pool_sizes.fivestar_exclusive[color as usize] {
+                            add_part(color, Pool::Fivestar);
+                        }
+                    }
+                }
+            }
+            TotalFivestar => {
+                for color in Color::iter() {
+                    for pool in &[Pool::Focus, Pool::Fivestar] {
+                        for _ in 0..banner.pool_size(*pool, color) {
+                            add_part(color, *pool);
+                        }
+                    }
+                }
+            }
+            RedFourstarFocus => add_part(Red, Pool::FourstarFocus),
+            BlueFourstarFocus => add_part(Blue, Pool::FourstarFocus),
+            GreenFourstarFocus => add_part(Green, Pool::FourstarFocus),
+            ColorlessFourstarFocus => add_part(Colorless, Pool::FourstarFocus),
+        }
+
+        custom_goal
+    }
+
+    /// The number of orbs that the goal has to be reached within, if any.
+    pub fn budget(&self) -> Option<u32> {
+        match self {
+            Goal::Custom(CustomGoal {
+                kind: GoalKind::Budget(orbs),
+                ..
+            }) => Some(*orbs),
+            _ => None,
+        }
+    }
+
+    /// Describes anything that makes the goal impossible or pointless on the
+    /// given banner, so that the reason can be shown instead of just not
+    /// running.
+    pub fn problems(&self, banner: &Banner) -> Vec<String> {
+        let mut problems = vec![];
+        let custom_goal = match self {
+            Goal::Preset(preset, count) => {
+                if !preset.is_available(banner) {
+                    problems.push("The banner doesn't have the units that this goal needs.".into());
+                }
+                if preset.uses_count() && *count == 0 {
+                    problems.push("The count needs to be at least 1.".into());
+                }
+                return problems;
+            }
+            Goal::Custom(custom_goal) => custom_goal,
+        };
+        if custom_goal.goals.is_empty() {
+            problems.push("Add at least one target to the goal.".into());
+            return problems;
+        }
+        for (index, part) in custom_goal.goals.iter().enumerate() {
+            if part.num_copies == 0 {
+                problems.push(format!("Target {} needs at least 1 copy.", index + 1));
+            }
+            if banner.pool_size(part.pool, part.unit_color) == 0 {
+                problems.push(format!(
+                    "Target {}: the banner has no {} {}.",
+                    index + 1,
+                    part.unit_color.to_string().to_lowercase(),
+                    pool_name(part.pool),
+                ));
+            }
+        }
+        match custom_goal.kind {
+            GoalKind::All | GoalKind::Budget(_) => {
+                for (index, part) in custom_goal.goals.iter().enumerate() {
+                    let size = banner.pool_size(part.pool, part.unit_color);
+                    if size > 0 && !custom_goal.is_part_possible(index, banner) {
+                        problems.push(format!(
+                            "Target {}: every target is a different unit, and the banner only \
+                             has {} {} {}.",
+                            index + 1,
+                            size,
+                            part.unit_color.to_string().to_lowercase(),
+                            pool_name(part.pool),
+                        ));
+                    }
+                }
+            }
+            GoalKind::Total(0) => problems.push("The total needs to be at least 1 copy.".into()),
+            GoalKind::AtLeast(0) => problems.push("At least 1 target needs to be finished.".into()),
+            GoalKind::Expression(ref text) => {
+                if let Err(message) = custom_goal.expression(text) {
+                    problems.push(message);
+                }
+            }
+            _ => {}
+        }
+        if problems.is_empty() && !self.is_available(banner) {
+            problems.push("The goal can't be reached on this banner.".into());
+        }
+        problems
+    }
+
+    /// Checks whether or not the goal is possible on the given banner.
+    pub fn is_available(&self, banner: &Banner) -> bool {
+        match self {
+            Goal::Custom(custom_goal) => match custom_goal.kind {
+                GoalKind::Any => custom_goal
+                    .goals
+                    .iter()
+                    .any(|part| banner.pool_size(part.pool, part.unit_color) > 0),
+                // Parts that can't be summoned don't help towards the total.
+                GoalKind::Total(copies) => {
+                    let available: u32 = custom_goal
+                        .goals
+                        .iter()
+                        .filter(|part| banner.pool_size(part.pool, part.unit_color) > 0)
+                        .map(|part| part.num_copies as u32)
+                        .sum();
+                    copies > 0 && available >= copies as u32
+                }
+                // Like with all of the parts, only as many parts of a color
+                // as there are units to go around can be finished.
+                GoalKind::AtLeast(parts) => {
+                    let possible = (0..custom_goal.goals.len())
+                        .filter(|&index| custom_goal.is_part_possible(index, banner))
+                        .count();
+                    parts > 0 && possible >= parts as usize
+                }
+                GoalKind::Expression(ref text) => match custom_goal.expression(text) {
+                    Ok(expr) => expr.holds(&|index| custom_goal.is_part_possible(index, banner)),
+                    Err(_) => false,
+                },
+                // Each color with parts needs at least one of them to be
+                // possible.
+                GoalKind::EachColor => {
+                    !custom_goal.goals.is_empty()
+                        && Color::iter().all(|color| {
+                            let mut parts = custom_goal
+                                .goals
+                                .iter()
+                                .filter(|part| part.unit_color == color)
+                                .peekable();
+                            parts.peek().is_none()
+                                || parts.any(|part| banner.pool_size(part.pool, color) > 0)
+                        })
+                }
+                // Each part is a different unit, so every one of them is only
+                // possible if there are enough units of each color in each
+                // pool to go around.
+                GoalKind::All | GoalKind::Budget(_) => {
+                    !custom_goal.goals.is_empty()
+                        && custom_goal.goals.iter().all(|part| {
+                            let parts = custom_goal
+                                .goals
+                                .iter()
+                                .filter(|other| {
+                                    other.pool == part.pool && other.unit_color == part.unit_color
+                                })
+                                .count();
+                            parts <= banner.pool_size(part.pool, part.unit_color)
+                        })
+                }
+            },
+            Goal::Preset(preset, _) => preset.is_available(banner),
+        }
+    }
+
+    /// Parses data from the representation used in query strings to share settings.
+    pub fn from_query_string(s: &str) -> Option<Self> {
+        let data = base64::decode(s).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+}
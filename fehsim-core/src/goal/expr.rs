@@ -0,0 +1,233 @@
+//! Goals that combine their parts in more ways than just any or all of them,
+//! written with the parts' numbers, for example:
+//!
+//! ```text
+//! 1 and (2 or 3)
+//! ```
+//!
+//! "and" takes priority over "or", so "1 or 2 and 3" means "1 or (2 and 3)".
+
+/// How deeply parentheses can be nested, so that a long enough expression
+/// can't overflow the stack while it's parsed or checked.
+const MAX_DEPTH: usize = 32;
+
+/// A way of combining goal parts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GoalExpr {
+    /// The goal part at the given index.
+    Part(usize),
+    All(Vec<GoalExpr>),
+    Any(Vec<GoalExpr>),
+}
+
+impl GoalExpr {
+    /// Checks whether the expression holds, given which parts are finished.
+    pub fn holds(&self, finished: &impl Fn(usize) -> bool) -> bool {
+        match self {
+            GoalExpr::Part(index) => finished(*index),
+            GoalExpr::All(exprs) => exprs.iter().all(|expr| expr.holds(finished)),
+            GoalExpr::Any(exprs) => exprs.iter().any(|expr| expr.holds(finished)),
+        }
+    }
+
+    /// Calls `visit` with each part that could still help the expression
+    /// hold, given which parts are finished.
+    pub fn for_each_needed(
+        &self,
+        finished: &impl Fn(usize) -> bool,
+        visit: &mut impl FnMut(usize),
+    ) {
+        if self.holds(finished) {
+            return;
+        }
+        match self {
+            GoalExpr::Part(index) => visit(*index),
+            GoalExpr::All(exprs) | GoalExpr::Any(exprs) => {
+                for expr in exprs {
+                    expr.for_each_needed(finished, visit);
+                }
+            }
+        }
+    }
+}
+
+/// Parses an expression for a goal with `part_count` parts, or describes the
+/// first problem with it.
+pub fn parse(text: &str, part_count: usize) -> Result<GoalExpr, String> {
+    let tokens = tokenize(text)?;
+    if tokens.is_empty() {
+        return Err("Enter the targets to combine, like \"1 and (2 or 3)\"".into());
+    }
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        part_count,
+        depth: 0,
+    };
+    let expr = parser.parse_or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected \"{}\"", token)),
+    }
+}
+
+/// Splits the text into numbers, words, and parentheses.
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if c.is_alphanumeric() {
+            let mut token = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_alphanumeric() {
+                    break;
+                }
+                token.push(c.to_ascii_lowercase());
+                chars.next();
+            }
+            tokens.push(token);
+        } else {
+            return Err(format!("Unexpected \"{}\"", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    part_count: usize,
+    /// How many parentheses the parser is inside of.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Takes the next token if it's the given one.
+    fn eat(&mut self, token: &str) -> bool {
+        if self.tokens.get(self.pos).map(String::as_str) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses targets joined by "or".
+    fn parse_or(&mut self) -> Result<GoalExpr, String> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.eat("or") {
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            GoalExpr::Any(exprs)
+        })
+    }
+
+    /// Parses targets joined by "and".
+    fn parse_and(&mut self) -> Result<GoalExpr, String> {
+        let mut exprs = vec![self.parse_single()?];
+        while self.eat("and") {
+            exprs.push(self.parse_single()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.remove(0)
+        } else {
+            GoalExpr::All(exprs)
+        })
+    }
+
+    /// Parses a single target number or a parenthesized expression.
+    fn parse_single(&mut self) -> Result<GoalExpr, String> {
+        let token = match self.tokens.get(self.pos) {
+            Some(token) => token,
+            None => return Err("The expression ends too early".into()),
+        };
+        self.pos += 1;
+        if token == "(" {
+            if self.depth == MAX_DEPTH {
+                return Err("Too many nested parentheses".into());
+            }
+            self.depth += 1;
+            let expr = self.parse_or()?;
+            self.depth -= 1;
+            if !self.eat(")") {
+                return Err("Missing \")\"".into());
+            }
+            return Ok(expr);
+        }
+        match token.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= self.part_count => {
+                Ok(GoalExpr::Part(number - 1))
+            }
+            Ok(number) => Err(format!("There's no target {}", number)),
+            Err(_) => Err(format!("Expected a target number, not \"{}\"", token)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn and_takes_priority_over_or() {
+        assert_eq!(
+            parse("1 or 2 and 3", 3),
+            Ok(GoalExpr::Any(vec![
+                GoalExpr::Part(0),
+                GoalExpr::All(vec![GoalExpr::Part(1), GoalExpr::Part(2)]),
+            ]))
+        );
+        assert_eq!(
+            parse("(1 OR 2) and 3", 3),
+            Ok(GoalExpr::All(vec![
+                GoalExpr::Any(vec![GoalExpr::Part(0), GoalExpr::Part(1)]),
+                GoalExpr::Part(2),
+            ]))
+        );
+    }
+
+    #[test]
+    fn only_existing_targets_are_allowed() {
+        assert_eq!(
+            parse("1 and 2", 2),
+            Ok(GoalExpr::All(vec![GoalExpr::Part(0), GoalExpr::Part(1)]))
+        );
+        assert_eq!(parse("1 and 3", 2), Err("There's no target 3".into()));
+        assert_eq!(parse("0", 2), Err("There's no target 0".into()));
+    }
+
+    #[test]
+    fn mistakes_are_described() {
+        assert!(parse("", 2).is_err());
+        assert_eq!(
+            parse("1 and", 2),
+            Err("The expression ends too early".into())
+        );
+        assert_eq!(parse("(1 or 2", 2), Err("Missing \")\"".into()));
+        assert_eq!(parse("1 2", 2), Err("Unexpected \"2\"".into()));
+        assert_eq!(parse("1 & 2", 2), Err("Unexpected \"&\"".into()));
+        assert_eq!(parse("1 xor 2", 2), Err("Unexpected \"xor\"".into()));
+        assert_eq!(
+            parse("and 1", 2),
+            Err("Expected a target number, not \"and\"".into())
+        );
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(parse(&nested(MAX_DEPTH), 1), Ok(GoalExpr::Part(0)));
+        assert_eq!(
+            parse(&nested(100_000), 1),
+            Err("Too many nested parentheses".into())
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! The summoning simulation behind the web app, usable on its own by anything
+//! else that wants the same numbers.
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use strum_macros::EnumIter;
+
+use serde::{Deserialize, Serialize};
+
+pub mod banner;
+
+pub mod catalog;
+
+pub mod goal;
+
+pub mod sim;
+
+pub mod weighted_choice;
+
+pub mod stats;
+
+pub mod counter;
+
+pub mod date;
+
+pub mod strategy;
+
+pub mod migrate;
+
+pub mod units;
+
+// Model
+
+#[repr(u8)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter, Serialize, Deserialize)]
+pub enum Color {
+    Red,
+    Blue,
+    Green,
+    Colorless,
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TryFrom<u8> for Color {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use Color::*;
+        Ok(match value {
+            0 => Red,
+            1 => Blue,
+            2 => Green,
+            3 => Colorless,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pool {
+    Focus,
+    Fivestar,
+    FourstarFocus,
+    FourstarSpecial,
+    Fourstar,
+    Threestar,
+}
+
+impl TryFrom<u8> for Pool {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use Pool::*;
+        Ok(match value {
+            0 => Focus,
+            1 => Fivestar,
+            2 => FourstarFocus,
+            3 => FourstarSpecial,
+            4 => Fourstar,
+            5 => Threestar,
+            _ => return Err(()),
+        })
+    }
+}
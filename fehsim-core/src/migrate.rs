@@ -0,0 +1,205 @@
+//! Reading settings that were saved in older versions of the permalink
+//! format. Each older version keeps a copy of the types that changed since
+//! then, and converts them to the current types, filling in defaults for
+//! anything that didn't exist yet.
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::banner::Banner;
+use crate::goal::{CustomGoal, Goal, GoalKind, GoalPart, GoalPreset};
+use crate::strategy::Strategy;
+use crate::{Color, Pool};
+
+/// The current version of the permalink format. Needs to be changed whenever
+/// the serialized representation of the banner, goal, or strategy changes
+/// after a release, along with adding a way to read the previous version here.
+pub const VERSION: u32 = 4;
+
+/// The oldest version that can still be read. Version 3 is the only one that
+/// was released before the current one.
+pub const OLDEST_VERSION: u32 = 3;
+
+/// Somewhere that a value saved with one of the versions of the format can be
+/// read from.
+pub trait Source {
+    fn read<T: DeserializeOwned>(self) -> Option<T>;
+}
+
+/// Values saved the same way as in permalinks.
+impl Source for &str {
+    fn read<T: DeserializeOwned>(self) -> Option<T> {
+        let data = base64::decode(self).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+}
+
+/// Parses a banner saved with the given version of the format.
+pub fn banner(source: impl Source, version: u32) -> Option<Banner> {
+    match version {
+        VERSION => source.read(),
+        3 => source.read::<v3::Banner>().map(Banner::from),
+        _ => None,
+    }
+}
+
+/// Parses a goal saved with the given version of the format.
+pub fn goal(source: impl Source, version: u32) -> Option<Goal> {
+    match version {
+        VERSION => source.read(),
+        3 => source.read::<v3::Goal>().map(Goal::from),
+        _ => None,
+    }
+}
+
+/// Parses a strategy saved with the given version of the format. Version 3
+/// didn't save strategies, so there's nothing older to read.
+pub fn strategy(source: impl Source, version: u32) -> Option<Strategy> {
+    match version {
+        VERSION => source.read(),
+        _ => None,
+    }
+}
+
+/// Converts starting rates in whole percents, as they were saved in version 3,
+/// to tenths of a percent.
+fn tenths(rates: (u8, u8)) -> (u16, u16) {
+    (rates.0 as u16 * 10, rates.1 as u16 * 10)
+}
+
+/// Version 3, from before banners had 4* focus sizes, sparks or pity settings.
+mod v3 {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct Banner {
+        pub focus_sizes: [i8; 4],
+        pub starting_rates: (u8, u8),
+        pub focus_charges: bool,
+        pub fourstar_focus: Option<Color>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum GoalKind {
+        Any,
+        All,
+    }
+
+    #[derive(Deserialize)]
+    pub struct GoalPart {
+        pub unit_color: Color,
+        pub num_copies: u8,
+        pub four_star: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub struct CustomGoal {
+        pub kind: GoalKind,
+        pub goals: Vec<GoalPart>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Goal {
+        Custom(CustomGoal),
+        Preset(GoalPreset, u8),
+    }
+
+    impl From<Banner> for super::Banner {
+        fn from(banner: Banner) -> Self {
+            let mut fourstar_focus_sizes = [0; 4];
+            if let Some(color) = banner.fourstar_focus {
+                fourstar_focus_sizes[color as usize] = 1;
+            }
+            super::Banner {
+                focus_sizes: banner.focus_sizes,
+                starting_rates: tenths(banner.starting_rates),
+                lower_rates: super::Banner::usual_lower_rates(tenths(banner.starting_rates)),
+                focus_charges: banner.focus_charges,
+                fourstar_focus_sizes,
+                ..super::Banner::default()
+            }
+        }
+    }
+
+    impl From<Goal> for super::Goal {
+        fn from(goal: Goal) -> Self {
+            match goal {
+                Goal::Custom(custom) => super::Goal::Custom(super::CustomGoal {
+                    kind: match custom.kind {
+                        GoalKind::Any => super::GoalKind::Any,
+                        GoalKind::All => super::GoalKind::All,
+                    },
+                    goals: custom
+                        .goals
+                        .into_iter()
+                        .map(|part| super::GoalPart {
+                            unit_color: part.unit_color,
+                            num_copies: part.num_copies as u16,
+                            pool: if part.four_star {
+                                Pool::FourstarFocus
+                            } else {
+                                Pool::Focus
+                            },
+                        })
+                        .collect(),
+                }),
+                Goal::Preset(preset, count) => super::Goal::Preset(preset, count as u16),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<T: serde::Serialize>(value: &T) -> String {
+        base64::encode(&bincode::serialize(value).unwrap())
+    }
+
+    #[test]
+    fn reads_version_3_links() {
+        // Structs are serialized like tuples of their fields.
+        let banner = encode(&([1i8, 2, 0, 1], (5u8, 3u8), false, Some(Color::Blue)));
+        let banner = super::banner(banner.as_str(), 3).unwrap();
+        assert_eq!(banner.focus_sizes, [1, 2, 0, 1]);
+        assert_eq!(banner.starting_rates, (50, 30));
+        assert!(!banner.focus_charges);
+        assert_eq!(banner.fourstar_focus_sizes, [0, 1, 0, 0]);
+
+        let goal = encode(&(0u32, (1u32, vec![(Color::Green, 2u8, true)])));
+        match super::goal(goal.as_str(), 3).unwrap() {
+            Goal::Custom(custom) => {
+                assert_eq!(custom.kind, GoalKind::All);
+                assert_eq!(custom.goals.len(), 1);
+                assert_eq!(custom.goals[0].unit_color, Color::Green);
+                assert_eq!(custom.goals[0].num_copies, 2);
+                assert_eq!(custom.goals[0].pool, Pool::FourstarFocus);
+            }
+            Goal::Preset(..) => panic!("expected a custom goal"),
+        }
+    }
+
+    #[test]
+    fn reads_current_links() {
+        let banner = Banner {
+            spark: true,
+            ..Banner::default()
+        };
+        let saved = encode(&banner);
+        assert_eq!(super::banner(saved.as_str(), VERSION), Some(banner));
+        let strategy = encode(&Strategy::default());
+        assert_eq!(
+            super::strategy(strategy.as_str(), VERSION),
+            Some(Strategy::default())
+        );
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let banner = encode(&Banner::default());
+        for &version in &[0, 1, 2, VERSION + 1] {
+            assert!(super::banner(banner.as_str(), version).is_none());
+        }
+    }
+}
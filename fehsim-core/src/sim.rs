@@ -0,0 +1,1224 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use rand::distributions::Distribution;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::weighted_choice::{WeightedIndex4, WeightedIndex6};
+
+use crate::banner::Banner;
+use crate::goal::expr::GoalExpr;
+use crate::goal::{CustomGoal, Goal, GoalKind};
+use crate::strategy::script::{self, CircleState, Rule};
+use crate::strategy::Strategy;
+use crate::{Color, Pool};
+
+pub mod exact;
+
+/// The results of a single run of the simulation.
+#[derive(Clone, Debug)]
+pub struct RunResult {
+    /// The number of orbs spent to reach the goal.
+    pub orbs: u32,
+    /// Whether the goal was actually reached, as opposed to stopping early.
+    pub reached_goal: bool,
+    /// The number of sparks claimed along the way.
+    pub sparks_claimed: u32,
+    /// Whether a spark gave a unit that the goal still needed, so that the
+    /// goal wouldn't have been reached by then without it.
+    pub needed_spark: bool,
+    /// The number of summons made to reach the goal.
+    pub summons: u32,
+    /// The number of summoning circles that at least one summon was made
+    /// from.
+    pub circles: u32,
+    /// The number of those summons that didn't cost any orbs.
+    pub free_summons: u32,
+    /// The number of 4* special rate units obtained along the way.
+    pub fourstar_specials: u32,
+    /// The number of off-focus 5* units obtained along the way.
+    pub pitybreakers: u32,
+    /// The number of 5* focus units summoned along the way, not counting
+    /// ones from sparks.
+    pub focus_units: u32,
+    /// The number of 4* units summoned along the way, including 4* focus
+    /// units and ones at the 4* special rate.
+    pub fourstars: u32,
+    /// The number of summons made only because a circle had no stones that
+    /// the strategy wanted to open.
+    pub forced_summons: u32,
+    /// The orbs spent on those summons, which is less than 5 for each when
+    /// some of them were free.
+    pub forced_orbs: u32,
+    /// The number of summoning circles, indexed by how many of their five
+    /// stones were a color that could contribute to the goal when opened.
+    pub circles_by_target_stones: [u32; 6],
+    /// The number of orbs spent by the time each goal part was finished, or
+    /// `None` for parts that weren't. Only filled in for goals with more than
+    /// one part.
+    pub part_orbs: Vec<Option<u32>>,
+    /// The goal part that was finished last, for goals with more than one
+    /// part that were reached.
+    pub last_part: Option<usize>,
+}
+
+/// The results of a pull session.
+struct SessionResult {
+    chosen_count: u32,
+    got_focus: bool,
+    nonfocus_count: u32,
+    focus_count: u32,
+    fourstar_special_count: u32,
+    fourstar_count: u32,
+    /// Whether a stone had to be opened even though it wasn't wanted, since
+    /// every circle requires at least one summon.
+    forced_summon: bool,
+}
+
+struct PullOrbResult {
+    got_non_focus: bool,
+    got_focus: bool,
+}
+
+/// A set of summoning rules that runs can be simulated under. The rest of the
+/// app only uses simulations through this, so that other rules can be added
+/// alongside the Fire Emblem Heroes ones without changing anything else.
+pub trait GachaSystem: fmt::Debug {
+    /// Simulates until reaching the current goal, or until the strategy says
+    /// to stop, and describes how the run went.
+    fn roll_until_goal(&mut self) -> RunResult;
+
+    /// Restarts the random number generator from the given seed.
+    fn reseed(&mut self, seed: u64);
+}
+
+/// Creates a simulation of the given settings, with its random numbers
+/// starting from the given seed.
+pub fn new_system(
+    banner: Banner,
+    goal: Goal,
+    strategy: Strategy,
+    seed: u64,
+) -> Box<dyn GachaSystem> {
+    Box::new(Sim::new(banner, goal, strategy, seed))
+}
+
+/// Creates a simulation of a plan that summons on each of the `steps` in
+/// order, all using the same strategy and sharing a budget of `budget` orbs.
+pub fn new_plan_system(
+    steps: Vec<(Banner, Goal)>,
+    strategy: Strategy,
+    budget: u32,
+    seed: u64,
+) -> Box<dyn GachaSystem> {
+    Box::new(PlanSim::new(steps, strategy, budget, seed))
+}
+
+/// The Fire Emblem Heroes summoning rules. Holds the information for a sequence
+/// of summoning sessions done until a certain goal is reached, and keeps some
+/// cached information in order to make the simulation as fast as possible.
+///
+/// The random number generator can be swapped out, for example for one that
+/// gives a fixed sequence of numbers so that the results of a run are known
+/// ahead of time.
+#[derive(Debug)]
+pub struct Sim<R = SmallRng> {
+    banner: Banner,
+    goal: CustomGoal,
+    /// The goal's expression for combining its parts, parsed ahead of time,
+    /// if it has one.
+    expression: Option<GoalExpr>,
+    strategy: Strategy,
+    /// The strategy's rules for which stones to open, parsed ahead of time.
+    rules: Vec<Rule>,
+    /// The colors of stones being opened in the current circle besides the
+    /// ones that the goal needs.
+    extra_colors: [bool; 4],
+    tables: RandTables,
+    /// Normally a fast non-cryptographic generator, since generating random
+    /// numbers is most of the work. It's only created once per simulation,
+    /// not per run.
+    rng: R,
+    goal_data: GoalData,
+    /// Number of summons made so far in the current run, used for the spark.
+    summon_count: u32,
+    /// Number of summons made since the last focus unit, used for the hard pity.
+    summons_since_focus: u32,
+    /// Number of sparks claimed so far in the current run.
+    sparks_claimed: u32,
+    /// Whether a spark has given a unit that the goal needed in the current
+    /// run.
+    needed_spark: bool,
+    /// The goal part finished most recently in the current run.
+    last_part: Option<usize>,
+}
+
+impl<R: Rng + SeedableRng + fmt::Debug> GachaSystem for Sim<R> {
+    fn roll_until_goal(&mut self) -> RunResult {
+        Sim::roll_until_goal(self)
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        self.rng = R::seed_from_u64(seed);
+    }
+}
+
+/// The total orb cost of choosing the given number of units from a session.
+/// The stones cost 5, 4, 4, 4, and 3 orbs in the order that they're opened.
+fn orb_cost(count: u32) -> u32 {
+    match count {
+        0 => 0,
+        1 => 5,
+        2 => 9,
+        3 => 13,
+        4 => 17,
+        5 => 20,
+        _ => panic!("Invalid orb cost: {}", count),
+    }
+}
+
+/// Runs two scenarios side by side in order to compare them. Both runs of
+/// each pair start from the same random numbers, so that most of the luck
+/// cancels out of the difference between them, and it takes far fewer runs to
+/// measure that difference accurately.
+#[derive(Debug)]
+pub struct PairedSim {
+    baseline: Box<dyn GachaSystem>,
+    current: Box<dyn GachaSystem>,
+    /// Picks the seed that both simulations use for each run.
+    rng: SmallRng,
+}
+
+impl PairedSim {
+    pub fn new(baseline: Box<dyn GachaSystem>, current: Box<dyn GachaSystem>, seed: u64) -> Self {
+        PairedSim {
+            baseline,
+            current,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Simulates a run of each scenario, returning the baseline's results
+    /// followed by the current one's.
+    pub fn roll_until_goal(&mut self) -> (RunResult, RunResult) {
+        let run_seed = self.rng.gen();
+        self.baseline.reseed(run_seed);
+        self.current.reseed(run_seed);
+        (
+            self.baseline.roll_until_goal(),
+            self.current.roll_until_goal(),
+        )
+    }
+}
+
+/// Runs a plan of several banners that are summoned on one after another,
+/// each with its own goal, where the orbs for all of them come out of one
+/// shared budget. A run reaches the goal only if every step is finished within
+/// the budget; it stops at the first step that isn't.
+#[derive(Debug)]
+pub struct PlanSim {
+    steps: Vec<Sim>,
+    budget: u32,
+}
+
+impl PlanSim {
+    pub fn new(steps: Vec<(Banner, Goal)>, strategy: Strategy, budget: u32, seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        PlanSim {
+            steps: steps
+                .into_iter()
+                .map(|(banner, goal)| Sim::new(banner, goal, strategy.clone(), rng.gen()))
+                .collect(),
+            budget,
+        }
+    }
+}
+
+impl GachaSystem for PlanSim {
+    fn roll_until_goal(&mut self) -> RunResult {
+        let mut total = RunResult {
+            orbs: 0,
+            reached_goal: true,
+            sparks_claimed: 0,
+            needed_spark: false,
+            summons: 0,
+            circles: 0,
+            free_summons: 0,
+            fourstar_specials: 0,
+            pitybreakers: 0,
+            focus_units: 0,
+            fourstars: 0,
+            forced_summons: 0,
+            forced_orbs: 0,
+            circles_by_target_stones: [0; 6],
+            part_orbs: vec![],
+            last_part: None,
+        };
+        for step in &mut self.steps {
+            let result = step.roll_within(Some(self.budget.saturating_sub(total.orbs)));
+            total.orbs += result.orbs;
+            total.sparks_claimed += result.sparks_claimed;
+            total.needed_spark |= result.needed_spark;
+            total.summons += result.summons;
+            total.circles += result.circles;
+            total.free_summons += result.free_summons;
+            total.fourstar_specials += result.fourstar_specials;
+            total.pitybreakers += result.pitybreakers;
+            total.focus_units += result.focus_units;
+            total.fourstars += result.fourstars;
+            total.forced_summons += result.forced_summons;
+            total.forced_orbs += result.forced_orbs;
+            for (total, count) in total
+                .circles_by_target_stones
+                .iter_mut()
+                .zip(&result.circles_by_target_stones)
+            {
+                *total += count;
+            }
+            if !result.reached_goal {
+                total.reached_goal = false;
+                break;
+            }
+        }
+        total
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        for step in &mut self.steps {
+            step.reseed(rng.gen());
+        }
+    }
+}
+
+/// Precalculated tables for the probabilities of units being randomly chosen.
+#[derive(Debug, Clone, Default)]
+struct RandTables {
+    pool_sizes: [[u8; 4]; 6],
+    /// Indexed by the number of rate increases, up to the one that takes the
+    /// 5* rate to 100%.
+    pool_dists: Vec<WeightedIndex6>,
+    /// How much the 5* rates have increased at each pity tier, in percent.
+    pity_pcts: Vec<f32>,
+    color_dists: [WeightedIndex4; 6],
+}
+
+/// Scratch space for representing the goal in a way that is faster to work with.
+#[derive(Debug, Clone)]
+struct GoalData {
+    pub is_fourstar_focus: bool,
+    pub color_needed: [bool; 4],
+    /// Copies needed of each focus unit that the goal wants, by color.
+    pub copies_needed: [Vec<u16>; 4],
+    /// Copies needed of each unit outside of the focus that the goal wants,
+    /// by pool and then color.
+    pub pool_copies_needed: [[Vec<u16>; 4]; 6],
+    /// Copies still needed in total, for goals that count them.
+    pub total_needed: u16,
+    /// Goal parts still needed to be finished, for goals that count them.
+    pub parts_needed: u8,
+    /// Where each goal part's copies are counted: its pool, color, and
+    /// position in that pool's list for the color.
+    pub part_slots: Vec<(Pool, Color, usize)>,
+}
+
+impl GoalData {
+    fn is_met(&self) -> bool {
+        self.color_needed == [false, false, false, false]
+    }
+
+    /// The list of copies needed for the given pool and color. 4* focus
+    /// units are counted along with the 5* focus units.
+    fn copies_needed(&self, pool: Pool, color: Color) -> &Vec<u16> {
+        match pool {
+            Pool::Focus | Pool::FourstarFocus => &self.copies_needed[color as usize],
+            _ => &self.pool_copies_needed[pool as usize][color as usize],
+        }
+    }
+
+    fn copies_needed_mut(&mut self, pool: Pool, color: Color) -> &mut Vec<u16> {
+        match pool {
+            Pool::Focus | Pool::FourstarFocus => &mut self.copies_needed[color as usize],
+            _ => &mut self.pool_copies_needed[pool as usize][color as usize],
+        }
+    }
+
+    /// Finds the goal part counted at the given position of a pool's list.
+    fn part_index(&self, pool: Pool, color: Color, slot: usize) -> Option<usize> {
+        let is_focus = |pool| pool == Pool::Focus || pool == Pool::FourstarFocus;
+        self.part_slots
+            .iter()
+            .position(|&(part_pool, part_color, part_slot)| {
+                (part_pool == pool || is_focus(part_pool) && is_focus(pool))
+                    && part_color == color
+                    && part_slot == slot
+            })
+    }
+
+    /// Whether the goal part at the given index has all of its copies.
+    fn is_part_finished(&self, index: usize) -> bool {
+        let (pool, color, slot) = self.part_slots[index];
+        self.copies_needed(pool, color)[slot] == 0
+    }
+
+    /// Whether any of the units that the goal still needs are of the given
+    /// color.
+    fn needs_color(&self, color: Color) -> bool {
+        let color = color as usize;
+        let needed = |copies: &Vec<u16>| copies.iter().any(|&copies| copies > 0);
+        needed(&self.copies_needed[color])
+            || self
+                .pool_copies_needed
+                .iter()
+                .any(|pool| needed(&pool[color]))
+    }
+}
+
+impl Sim {
+    /// Creates a new simulator for the given banner and goal, doing some
+    /// moderately expensive initialization. Avoid running in a hot loop, but
+    /// it's not a problem to call somewhat frequently.
+    pub fn new(banner: Banner, goal: Goal, strategy: Strategy, seed: u64) -> Self {
+        Sim::with_rng(banner, goal, strategy, SmallRng::seed_from_u64(seed))
+    }
+
+    /// Picks a seed for when the user doesn't choose one.
+    pub fn random_seed() -> u64 {
+        SmallRng::from_entropy().gen()
+    }
+}
+
+impl<R: Rng> Sim<R> {
+    /// Creates a new simulator that gets its random numbers from the given
+    /// generator.
+    pub fn with_rng(banner: Banner, goal: Goal, strategy: Strategy, rng: R) -> Self {
+        let goal = goal.as_custom(&banner);
+        let is_fourstar_focus = banner.has_fourstar_focus();
+        let expression = match &goal.kind {
+            GoalKind::Expression(text) => goal.expression(text).ok(),
+            _ => None,
+        };
+        let mut sim = Sim {
+            banner,
+            goal,
+            expression,
+            rules: strategy.rules().unwrap_or_default(),
+            extra_colors: [false; 4],
+            strategy,
+            tables: RandTables::default(),
+            rng,
+            goal_data: GoalData {
+                is_fourstar_focus,
+                color_needed: [false; 4],
+                copies_needed: Default::default(),
+                pool_copies_needed: Default::default(),
+                total_needed: 0,
+                parts_needed: 0,
+                part_slots: vec![],
+            },
+            summon_count: 0,
+            summons_since_focus: 0,
+            sparks_claimed: 0,
+            needed_spark: false,
+            last_part: None,
+        };
+        sim.init_probability_tables();
+        sim
+    }
+
+    /// Initializes the precalculated tables used for fast random sampling.
+    fn init_probability_tables(&mut self) {
+        let pool_sizes = self.banner.pool_sizes;
+        self.tables.pool_sizes = [
+            [0, 0, 0, 0],
+            pool_sizes.fivestar,
+            [0, 0, 0, 0],
+            pool_sizes.fourstar_special,
+            pool_sizes.fourstar,
+            pool_sizes.threestar,
+        ];
+        for i in 0..4 {
+            self.tables.pool_sizes[0][i] = self.banner.focus_sizes[i].max(0) as u8;
+        }
+        for i in 0..4 {
+            self.tables.pool_sizes[2][i] = self.banner.fourstar_focus_sizes[i].max(0) as u8;
+        }
+
+        for color in 0..6 {
+            self.tables.color_dists[color] = WeightedIndex4::new(self.tables.pool_sizes[color]);
+        }
+
+        let tiers = 0..=self.banner.max_pity_increases();
+        self.tables.pity_pcts = tiers.clone().map(|incr| self.pity_pct(incr)).collect();
+        self.tables.pool_dists = tiers
+            .map(|incr| WeightedIndex6::new(self.probabilities(incr)))
+            .collect();
+    }
+
+    // Initializes the internal representation of a goal.
+    fn init_goal_data(&mut self) {
+        self.goal_data.color_needed = [false, false, false, false];
+        self.goal_data.is_fourstar_focus = false;
+        for i in 0..4 {
+            self.goal_data.copies_needed[i].clear();
+            for pool in 0..6 {
+                self.goal_data.pool_copies_needed[pool][i].clear();
+            }
+        }
+        self.goal_data.total_needed = match self.goal.kind {
+            GoalKind::Total(copies) => copies,
+            _ => 0,
+        };
+        self.goal_data.parts_needed = match self.goal.kind {
+            GoalKind::AtLeast(parts) => parts,
+            _ => 0,
+        };
+        self.goal_data.part_slots.clear();
+        for &goal in &self.goal.goals {
+            if goal.pool == Pool::FourstarFocus {
+                self.goal_data.is_fourstar_focus = true;
+            }
+            let copies_needed = self.goal_data.copies_needed_mut(goal.pool, goal.unit_color);
+            copies_needed.push(goal.num_copies);
+            let slot = copies_needed.len() - 1;
+            self.goal_data
+                .part_slots
+                .push((goal.pool, goal.unit_color, slot));
+            self.goal_data.color_needed[goal.unit_color as usize] = true;
+        }
+        if self.expression.is_some() {
+            self.update_needed_colors();
+        }
+    }
+
+    /// Simulates until reaching the current goal, then returns # of orbs used
+    /// along with some statistics about the units obtained along the way.
+    pub fn roll_until_goal(&mut self) -> RunResult {
+        self.roll_within(None)
+    }
+
+    /// Simulates like `roll_until_goal`, but also gives up once `limit` orbs
+    /// have been spent, on top of any budget that the goal has.
+    pub fn roll_within(&mut self, limit: Option<u32>) -> RunResult {
+        let mut pity_count = self.banner.starting_pity as u32;
+        let mut orb_count = 0;
+        let mut fourstar_specials = 0;
+        let mut pitybreakers = 0;
+        let mut focus_units = 0;
+        let mut fourstars = 0;
+        let mut forced_summons = 0;
+        let mut forced_orbs = 0;
+        let mut circles = 0;
+        let mut circles_by_target_stones = [0; 6];
+        let pity_interval = self.banner.pity_interval.max(1) as u32;
+        let max_pity_incr = self.banner.max_pity_increases();
+        let partial_reset = self.banner.partial_reset_summons();
+        let mut focus_charges = 0;
+        // Daily free summons work the same way as tickets, since the order
+        // that they're used in doesn't matter.
+        let mut tickets_left = self.banner.tickets as u32;
+        if self.banner.daily_free_summon {
+            tickets_left += self.banner.duration_days as u32;
+        }
+        let mut free_summons = 0;
+        let mut part_orbs = if self.goal.goals.len() > 1 {
+            vec![None; self.goal.goals.len()]
+        } else {
+            vec![]
+        };
+        self.summon_count = 0;
+        self.summons_since_focus = self.banner.starting_pity as u32;
+        self.sparks_claimed = 0;
+        self.needed_spark = false;
+        self.last_part = None;
+        self.init_goal_data();
+        let budget = match (&self.goal.kind, limit) {
+            (GoalKind::Budget(orbs), Some(limit)) => Some((*orbs).min(limit)),
+            (GoalKind::Budget(orbs), None) => Some(*orbs),
+            (_, limit) => limit,
+        };
+        loop {
+            let pity_incr = (pity_count / pity_interval).min(max_pity_incr) as usize;
+            self.extra_colors = script::extra_colors(
+                &self.rules,
+                &CircleState {
+                    pity_pct: self.tables.pity_pcts[pity_incr],
+                    summons: self.summon_count,
+                    orbs: orb_count,
+                    spark: (self.summon_count + self.banner.starting_spark as u32) % 40,
+                },
+            );
+            let samples = [
+                self.sample(pity_incr, focus_charges == 3),
+                self.sample(pity_incr, focus_charges == 3),
+                self.sample(pity_incr, focus_charges == 3),
+                self.sample(pity_incr, focus_charges == 3),
+                self.sample(pity_incr, focus_charges == 3),
+            ];
+            let target_stones = samples
+                .iter()
+                .filter(|sample| self.may_match_goal(sample.1))
+                .count();
+            circles_by_target_stones[target_stones] += 1;
+            let SessionResult {
+                chosen_count,
+                got_focus,
+                nonfocus_count,
+                focus_count,
+                fourstar_special_count,
+                fourstar_count,
+                forced_summon,
+            } = self.session_select(&samples);
+            circles += 1;
+            if forced_summon {
+                forced_summons += 1;
+            }
+            pity_count += chosen_count;
+            fourstar_specials += fourstar_special_count;
+            pitybreakers += nonfocus_count;
+            focus_units += focus_count;
+            fourstars += fourstar_count;
+            pity_count =
+                self.banner
+                    .pity_reset
+                    .apply(pity_count, partial_reset, got_focus, nonfocus_count);
+            if got_focus && focus_charges == 3 {
+                focus_charges = 0;
+            }
+            if self.banner.has_focus_charges() {
+                focus_charges = (focus_charges + nonfocus_count).min(3);
+                if got_focus {
+                    focus_charges = 0;
+                }
+            }
+            let first_summon_free =
+                if self.banner.free_first_summon && self.summon_count == chosen_count {
+                    true
+                } else if tickets_left > 0 {
+                    tickets_left -= 1;
+                    true
+                } else {
+                    false
+                };
+            orb_count += orb_cost(chosen_count);
+            if first_summon_free {
+                // The first stone of a circle would have cost 5 orbs.
+                orb_count -= 5;
+                free_summons += 1;
+            } else if forced_summon {
+                // The forced stone is the only one opened in its circle.
+                forced_orbs += 5;
+            }
+            for (index, orbs) in part_orbs.iter_mut().enumerate() {
+                if orbs.is_none() && self.goal_data.is_part_finished(index) {
+                    *orbs = Some(orb_count);
+                }
+            }
+            let out_of_orbs = matches!(budget, Some(budget) if orb_count >= budget);
+            if self.goal_data.is_met() || self.stopped_at_spark() || out_of_orbs {
+                let reached_goal =
+                    self.goal_data.is_met() && budget.iter().all(|&budget| orb_count <= budget);
+                return RunResult {
+                    orbs: orb_count,
+                    summons: self.summon_count,
+                    circles,
+                    free_summons,
+                    fourstar_specials,
+                    pitybreakers,
+                    focus_units,
+                    fourstars,
+                    forced_summons,
+                    forced_orbs,
+                    circles_by_target_stones,
+                    part_orbs,
+                    sparks_claimed: self.sparks_claimed,
+                    needed_spark: self.needed_spark,
+                    last_part: if reached_goal && self.goal.goals.len() > 1 {
+                        self.last_part
+                    } else {
+                        None
+                    },
+                    reached_goal,
+                };
+            }
+        }
+    }
+
+    /// Given a session with five randomly-selected units, decides which ones
+    /// would be chosen to achieve the current goal, then evaluates the results
+    /// of choosing them.
+    fn session_select(&mut self, samples: &[(Pool, Color); 5]) -> SessionResult {
+        let mut result = SessionResult {
+            chosen_count: 0,
+            got_focus: false,
+            nonfocus_count: 0,
+            focus_count: 0,
+            fourstar_special_count: 0,
+            fourstar_count: 0,
+            forced_summon: false,
+        };
+        for (i, &sample) in samples.iter().enumerate() {
+            let mut sample = sample;
+            let wanted = self.should_open(sample.1);
+            let forced = i == 4 && result.chosen_count == 0;
+            if wanted || forced {
+                result.chosen_count += 1;
+                result.forced_summon = !wanted;
+                // Colors without focus units can't give the guaranteed unit,
+                // so it waits for the next stone of a color that has them.
+                if self.hard_pity_reached() && self.banner.focus_sizes[sample.1 as usize] > 0 {
+                    sample.0 = Pool::Focus;
+                }
+                let pull_result = self.pull_orb(sample);
+                if pull_result.got_focus {
+                    self.summons_since_focus = 0;
+                } else {
+                    self.summons_since_focus += 1;
+                }
+                result.got_focus |= pull_result.got_focus;
+                result.nonfocus_count += if pull_result.got_non_focus { 1 } else { 0 };
+                result.focus_count += if pull_result.got_focus { 1 } else { 0 };
+                match sample.0 {
+                    Pool::FourstarSpecial => {
+                        result.fourstar_special_count += 1;
+                        result.fourstar_count += 1;
+                    }
+                    Pool::FourstarFocus | Pool::Fourstar => result.fourstar_count += 1,
+                    _ => {}
+                }
+                self.summon_count += 1;
+                let spark_progress = (self.summon_count + self.banner.starting_spark as u32) % 40;
+                if self.banner.spark && spark_progress == 0 && !self.goal_data.is_met() {
+                    self.claim_spark();
+                }
+                if self.goal_data.is_met() || self.stopped_at_spark() {
+                    return result;
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether enough summons have been made without a focus unit that the
+    /// next one is guaranteed to be a focus unit.
+    fn hard_pity_reached(&self) -> bool {
+        match self.banner.hard_pity {
+            Some(threshold) => self.summons_since_focus >= threshold as u32,
+            None => false,
+        }
+    }
+
+    /// Whether the strategy is to open a stone of the given color.
+    fn should_open(&self, color: Color) -> bool {
+        self.may_match_goal(color) || self.extra_colors[color as usize]
+    }
+
+    /// Specifies whether the color has the possibility of contributing towards
+    /// completing the current goal.
+    fn may_match_goal(&self, color: Color) -> bool {
+        self.goal_data.color_needed[color as usize]
+    }
+
+    /// Evaluates the result of selecting the given sample.
+    fn pull_orb(&mut self, sample: (Pool, Color)) -> PullOrbResult {
+        let (pool, color) = sample;
+        let result = PullOrbResult {
+            got_focus: pool == Pool::Focus,
+            got_non_focus: pool == Pool::Fivestar,
+        };
+        if !self.goal_data.color_needed[color as usize] {
+            return result;
+        }
+        match pool {
+            Pool::Focus => {
+                let focus_count = self.banner.focus_sizes[color as usize];
+                let which_unit = self.rng.gen::<usize>() % focus_count as usize;
+                self.receive_unit(Pool::Focus, color, which_unit);
+            }
+            Pool::FourstarFocus => {
+                if self.goal_data.is_fourstar_focus {
+                    // The 4* focus units are treated as the first few focus
+                    // units of their color.
+                    let fourstar_count = self.banner.fourstar_focus_sizes[color as usize];
+                    let which_unit = self.rng.gen::<usize>() % fourstar_count as usize;
+                    self.receive_unit(Pool::Focus, color, which_unit);
+                }
+            }
+            _ => {
+                // Units outside of the focus are only told apart when the
+                // goal wants one of them, so that other goals get the same
+                // results for the same seed.
+                let copies_needed = &self.goal_data.pool_copies_needed[pool as usize];
+                if copies_needed[color as usize]
+                    .iter()
+                    .any(|&copies| copies > 0)
+                {
+                    let pool_size = self.tables.pool_sizes[pool as usize][color as usize];
+                    let which_unit = self.rng.gen::<usize>() % pool_size as usize;
+                    self.receive_unit(pool, color, which_unit);
+                }
+            }
+        }
+        result
+    }
+
+    /// Updates the goal progress after receiving a copy of the given unit.
+    /// The focus pool's units include the 4* focus units.
+    fn receive_unit(&mut self, pool: Pool, color: Color, which_unit: usize) {
+        // Finished parts are kept in the lists, at zero copies, so that
+        // each part stays the same unit.
+        let copies_needed = self.goal_data.copies_needed_mut(pool, color);
+        if which_unit < copies_needed.len() && copies_needed[which_unit] > 0 {
+            copies_needed[which_unit] -= 1;
+            if copies_needed[which_unit] == 0 {
+                self.last_part = self.goal_data.part_index(pool, color, which_unit);
+                self.finish_part(color);
+            }
+            self.count_copy();
+        }
+    }
+
+    /// Updates the total number of copies needed after receiving a copy of
+    /// any of the goal's units.
+    fn count_copy(&mut self) {
+        if let GoalKind::Total(_) = self.goal.kind {
+            self.goal_data.total_needed = self.goal_data.total_needed.saturating_sub(1);
+            if self.goal_data.total_needed == 0 {
+                self.goal_data.color_needed = [false, false, false, false];
+            }
+        }
+    }
+
+    /// Updates which colors are still needed after a goal part of the given
+    /// color is finished.
+    fn finish_part(&mut self, color: Color) {
+        match self.goal.kind {
+            GoalKind::Any => self.goal_data.color_needed = [false, false, false, false],
+            GoalKind::EachColor => self.goal_data.color_needed[color as usize] = false,
+            GoalKind::AtLeast(_) => {
+                self.goal_data.parts_needed = self.goal_data.parts_needed.saturating_sub(1);
+                if self.goal_data.parts_needed == 0 {
+                    self.goal_data.color_needed = [false, false, false, false];
+                } else {
+                    self.goal_data.color_needed[color as usize] = self.goal_data.needs_color(color);
+                }
+            }
+            GoalKind::All | GoalKind::Budget(_) | GoalKind::Total(_) => {
+                self.goal_data.color_needed[color as usize] = self.goal_data.needs_color(color);
+            }
+            GoalKind::Expression(_) => self.update_needed_colors(),
+        }
+    }
+
+    /// Works out which colors are still needed from the goal's expression.
+    fn update_needed_colors(&mut self) {
+        let goal_data = &self.goal_data;
+        let mut color_needed = [false; 4];
+        if let Some(expression) = &self.expression {
+            expression.for_each_needed(&|index| goal_data.is_part_finished(index), &mut |index| {
+                color_needed[goal_data.part_slots[index].1 as usize] = true
+            });
+        }
+        self.goal_data.color_needed = color_needed;
+    }
+
+    /// Claims the spark for whichever goal unit is closest to being completed.
+    fn claim_spark(&mut self) {
+        let mut best: Option<(Color, usize, u16)> = None;
+        for color in 0..4 {
+            if !self.goal_data.color_needed[color] {
+                continue;
+            }
+            for (which_unit, &copies) in self.goal_data.copies_needed[color].iter().enumerate() {
+                if copies > 0 && best.iter().all(|&(_, _, best_copies)| copies < best_copies) {
+                    best = Some((Color::try_from(color as u8).unwrap(), which_unit, copies));
+                }
+            }
+        }
+        if let Some((color, which_unit, _)) = best {
+            self.receive_unit(Pool::Focus, color, which_unit);
+            self.needed_spark = true;
+        }
+        self.sparks_claimed += 1;
+    }
+
+    /// Whether the strategy is to stop summoning now, even if the goal hasn't
+    /// been reached.
+    fn stopped_at_spark(&self) -> bool {
+        self.strategy.stop_after_spark && self.sparks_claimed > 0
+    }
+
+    /// Chooses a weighted random unit from the summoning pool. `pity_incr` is the
+    /// number of times that the 5* rates have increased.
+    fn sample(&mut self, pity_incr: usize, focus_charge_active: bool) -> (Pool, Color) {
+        let pool = self.tables.pool_dists[pity_incr].sample(&mut self.rng) as u8;
+        let mut pool = Pool::try_from(pool).unwrap();
+        if focus_charge_active && pool == Pool::Fivestar {
+            pool = Pool::Focus;
+        }
+
+        let color = self.tables.color_dists[pool as usize].sample(&mut self.rng) as u8;
+        let color = Color::try_from(color).unwrap();
+        (pool, color)
+    }
+
+    /// Calculates the actual probabilities of selecting a unit from each of the
+    /// possible pools after a certain number of rate increases.
+    fn probabilities(&self, pity_incr: u32) -> [f32; 6] {
+        let bases = self.bases();
+        let pity_pct = self.pity_pct(pity_incr);
+
+        // How much of a share of the two rates goes to the first, where an
+        // empty pair has nothing to share out.
+        let ratio = |first: f32, second: f32| {
+            if first + second > 0.0 {
+                first / (first + second)
+            } else {
+                0.0
+            }
+        };
+
+        let mut probabilities = bases;
+        let focus_ratio = ratio(bases[Pool::Focus as usize], bases[Pool::Fivestar as usize]);
+        probabilities[Pool::Focus as usize] += pity_pct * focus_ratio;
+        probabilities[Pool::Fivestar as usize] += pity_pct * (1.0 - focus_ratio);
+
+        let lower_ratio = ratio(
+            bases[Pool::Fourstar as usize],
+            bases[Pool::Threestar as usize],
+        );
+        probabilities[Pool::Fourstar as usize] -= pity_pct * lower_ratio;
+        probabilities[Pool::Threestar as usize] -= pity_pct * (1.0 - lower_ratio);
+        probabilities
+    }
+
+    /// Calculates how much the 5* rates have increased, in percent, after a
+    /// certain number of rate increases.
+    fn pity_pct(&self, pity_incr: u32) -> f32 {
+        let bases = self.bases();
+        let pity_pct = if self.banner.pity_step > 0 && pity_incr >= self.banner.max_pity_increases()
+        {
+            100.0 - bases[Pool::Focus as usize] - bases[1]
+        } else {
+            pity_incr as f32 * self.banner.pity_step as f32 / 10.0
+        };
+        // The increase only comes out of the regular 4* and 3* pools, so it
+        // can't be any larger than those put together.
+        pity_pct.min(bases[Pool::Fourstar as usize] + bases[Pool::Threestar as usize])
+    }
+
+    /// Gives the base probabilities of selecting a unit from each pool.
+    fn bases(&self) -> [f32; 6] {
+        self.banner.base_rates()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use rand::RngCore;
+
+    use super::*;
+    use crate::banner::PityReset;
+    use crate::goal::GoalPreset;
+    use crate::strategy::StoneChoice;
+    use crate::weighted_choice::WeightedIndex;
+
+    /// Gives a fixed sequence of numbers, so that every stone of a run can be
+    /// chosen ahead of time.
+    #[derive(Debug, Default)]
+    struct FixedRng(VecDeque<u32>);
+
+    impl RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+                .pop_front()
+                .expect("the run used more numbers than expected")
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.next_u32() as u64
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    /// Finds a number that makes each of the samplers give an entry that
+    /// `wanted` accepts.
+    fn number_for<const N: usize>(
+        dists: &[WeightedIndex<N>],
+        wanted: impl Fn(usize, usize) -> bool,
+    ) -> u32 {
+        (0..1 << 24)
+            .step_by(7)
+            .map(|x| x << 8)
+            .find(|&number| {
+                dists.iter().enumerate().all(|(index, dist)| {
+                    wanted(index, dist.sample(&mut FixedRng(vec![number].into())))
+                })
+            })
+            .expect("no number gives the wanted entries")
+    }
+
+    /// Numbers for the stones that the tests need.
+    struct Stones {
+        /// A 4* or 3* unit at the first few pity levels.
+        other: u32,
+        /// An off-focus 5* unit after one or two rate increases, but not
+        /// before any.
+        pitybreaker: u32,
+        /// A focus unit at the first few pity levels.
+        focus: u32,
+        /// A red unit from the focus pool.
+        red: u32,
+    }
+
+    impl Stones {
+        fn new(sim: &Sim<FixedRng>) -> Self {
+            let pools = &sim.tables.pool_dists[..10];
+            let is_fivestar =
+                |pool| pool == Pool::Focus as usize || pool == Pool::Fivestar as usize;
+            Stones {
+                other: number_for(pools, |_, pool| !is_fivestar(pool)),
+                pitybreaker: number_for(&pools[..3], |incr, pool| {
+                    if incr == 0 {
+                        !is_fivestar(pool)
+                    } else {
+                        pool == Pool::Fivestar as usize
+                    }
+                }),
+                focus: number_for(pools, |_, pool| pool == Pool::Focus as usize),
+                red: number_for(&sim.tables.color_dists[..1], |_, color| {
+                    color == Color::Red as usize
+                }),
+            }
+        }
+    }
+
+    fn sim(banner: Banner) -> Sim<FixedRng> {
+        let strategy = Strategy {
+            stones: StoneChoice::OpenAll,
+            ..Strategy::default()
+        };
+        let goal = Goal::Preset(GoalPreset::RedFocus, 1);
+        Sim::with_rng(banner, goal, strategy, FixedRng::default())
+    }
+
+    fn banner() -> Banner {
+        Banner {
+            focus_charges: false,
+            ..Banner::default()
+        }
+    }
+
+    /// The numbers for a circle whose stones come from the given pools, all
+    /// red.
+    fn circle(pools: [u32; 5], red: u32) -> Vec<u32> {
+        pools.iter().flat_map(|&pool| vec![pool, red]).collect()
+    }
+
+    #[test]
+    fn pitybreaker_resets_the_rate() {
+        for &(pity_reset, pitybreakers) in &[
+            (PityReset::Full, 1),
+            (PityReset::Partial, 1),
+            (PityReset::Never, 2),
+        ] {
+            let mut sim = sim(Banner {
+                pity_reset,
+                ..banner()
+            });
+            let Stones {
+                other,
+                pitybreaker,
+                focus,
+                red,
+            } = Stones::new(&sim);
+            let mut numbers = circle([other; 5], red);
+            // A rate increase has happened, so this is an off-focus 5* unit.
+            numbers.extend(circle([pitybreaker, other, other, other, other], red));
+            // Only an off-focus 5* unit if the rate wasn't reset. The focus
+            // unit after it finishes the goal, as the only focus unit.
+            numbers.extend(circle([pitybreaker, focus, other, other, other], red));
+            numbers.push(0);
+            sim.rng.0 = numbers.into();
+
+            let result = sim.roll_within(None);
+            assert!(result.reached_goal);
+            assert_eq!(result.pitybreakers, pitybreakers, "{:?}", pity_reset);
+            assert_eq!(result.focus_units, 1);
+            assert_eq!(result.summons, 12);
+            assert_eq!(result.orbs, 20 + 20 + 9);
+            assert!(sim.rng.0.is_empty());
+        }
+    }
+
+    #[test]
+    fn guarantee_waits_for_a_color_with_focus_units() {
+        let mut sim = sim(Banner {
+            focus_sizes: [1, 0, 0, 0],
+            hard_pity: Some(5),
+            ..banner()
+        });
+        let Stones { other, red, .. } = Stones::new(&sim);
+        let blue = number_for(
+            &sim.tables.color_dists[Pool::Fourstar as usize..],
+            |_, color| color == Color::Blue as usize,
+        );
+        let mut numbers = circle([other; 5], red);
+        // Blue has no focus units, so the guarantee goes to the red stone
+        // after it.
+        numbers.extend(vec![other, blue]);
+        numbers.extend([other, red].repeat(4));
+        numbers.push(0);
+        sim.rng.0 = numbers.into();
+
+        let result = sim.roll_within(None);
+        assert!(result.reached_goal);
+        assert_eq!(result.focus_units, 1);
+        assert_eq!(result.summons, 7);
+        assert!(sim.rng.0.is_empty());
+    }
+
+    #[test]
+    fn rate_increases_follow_the_step() {
+        let usual = sim(banner());
+        assert_eq!(usual.tables.pity_pcts.len(), 26);
+        assert_eq!(usual.tables.pity_pcts[24], 12.0);
+        assert_eq!(usual.banner.partial_reset_summons(), 20);
+
+        // 1% every 5 summons reaches 12.5% after 13 increases, and an
+        // off-focus 5* takes away two of them.
+        let faster = sim(Banner {
+            pity_step: 10,
+            ..banner()
+        });
+        let bases = faster.bases();
+        assert_eq!(faster.tables.pity_pcts.len(), 14);
+        assert_eq!(faster.tables.pity_pcts[12], 12.0);
+        assert_eq!(faster.tables.pity_pcts[13], 100.0 - bases[0] - bases[1]);
+        assert_eq!(faster.banner.partial_reset_summons(), 10);
+
+        let flat = sim(Banner {
+            pity_step: 0,
+            ..banner()
+        });
+        assert_eq!(flat.tables.pity_pcts, vec![0.0]);
+    }
+
+    #[test]
+    fn rates_without_room_for_lower_rarities_stay_numbers() {
+        let banner = Banner {
+            starting_rates: (1000, 0),
+            ..banner()
+        };
+        assert!(!banner.problems().is_empty());
+        let sim = sim(banner);
+        for incr in 0..=sim.banner.max_pity_increases() {
+            let probabilities = sim.probabilities(incr);
+            assert!(
+                probabilities.iter().all(|p| p.is_finite()),
+                "{:?}",
+                probabilities
+            );
+            assert_eq!(probabilities[Pool::Focus as usize], 100.0);
+        }
+    }
+
+    #[test]
+    fn pools_without_a_rate_cant_be_goals() {
+        use crate::goal::{CustomGoal, GoalKind, GoalPart};
+
+        let banner = Banner {
+            lower_rates: (0, 360),
+            ..banner()
+        };
+        assert_eq!(banner.pool_size(Pool::Fourstar, Color::Blue), 0);
+        let goal = Goal::Custom(CustomGoal {
+            kind: GoalKind::All,
+            goals: vec![GoalPart {
+                unit_color: Color::Blue,
+                num_copies: 1,
+                pool: Pool::Fourstar,
+            }],
+        });
+        assert!(!goal.is_available(&banner));
+        assert!(!goal.problems(&banner).is_empty());
+    }
+
+    #[test]
+    fn spark_is_claimed_at_40_summons() {
+        let mut sim = sim(Banner {
+            spark: true,
+            ..banner()
+        });
+        let Stones { other, red, .. } = Stones::new(&sim);
+        sim.rng.0 = (0..8).flat_map(|_| circle([other; 5], red)).collect();
+
+        let result = sim.roll_within(None);
+        assert!(result.reached_goal);
+        assert_eq!(result.sparks_claimed, 1);
+        assert!(result.needed_spark);
+        assert_eq!(result.focus_units, 0);
+        assert_eq!(result.summons, 40);
+        assert_eq!(result.orbs, 8 * 20);
+        assert!(sim.rng.0.is_empty());
+    }
+
+    #[test]
+    fn free_forced_summons_cost_nothing() {
+        let banner = Banner {
+            tickets: 1,
+            ..banner()
+        };
+        let goal = Goal::Preset(GoalPreset::RedFocus, 1);
+        let mut sim = Sim::with_rng(banner, goal, Strategy::default(), FixedRng::default());
+        let Stones {
+            other, focus, red, ..
+        } = Stones::new(&sim);
+        let blue = number_for(
+            &sim.tables.color_dists[Pool::Fourstar as usize..],
+            |_, color| color == Color::Blue as usize,
+        );
+        // Two circles with nothing red, the first of which uses the ticket.
+        let mut numbers = [other, blue].repeat(10);
+        numbers.extend(vec![focus, red]);
+        numbers.extend([other, blue].repeat(4));
+        numbers.push(0);
+        sim.rng.0 = numbers.into();
+
+        let result = sim.roll_within(None);
+        assert!(result.reached_goal);
+        assert_eq!(result.forced_summons, 2);
+        assert_eq!(result.forced_orbs, 5);
+        assert_eq!(result.orbs, 10);
+        assert!(sim.rng.0.is_empty());
+    }
+
+    #[test]
+    fn spark_counts_summons_already_made() {
+        let mut sim = sim(Banner {
+            spark: true,
+            starting_spark: 38,
+            ..banner()
+        });
+        let Stones { other, red, .. } = Stones::new(&sim);
+        sim.rng.0 = circle([other; 5], red).into();
+
+        let result = sim.roll_within(None);
+        assert!(result.reached_goal);
+        assert_eq!(result.sparks_claimed, 1);
+        assert_eq!(result.summons, 2);
+        assert_eq!(result.orbs, 9);
+    }
+}
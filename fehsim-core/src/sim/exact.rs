@@ -0,0 +1,429 @@
+//! Exact calculation of the distribution of orbs spent, for goals that are
+//! simple enough for the whole state of a run to fit in a small table.
+//!
+//! Between summoning circles, a run is described by just its pity count and
+//! the number of copies that are still needed. Instead of simulating runs one
+//! at a time, this tracks the probability of being in each of those states as
+//! orbs are spent, which gives results without any random noise.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::banner::Banner;
+use crate::counter::Counter;
+use crate::goal::{Goal, GoalKind};
+use crate::strategy::{StoneChoice, Strategy};
+use crate::Pool;
+
+use super::{orb_cost, Sim};
+
+/// The total count that the calculated distribution is scaled to, so that it
+/// can be shown the same way as simulated results.
+const TOTAL_COUNT: f64 = 1_000_000_000.0;
+
+/// The calculation stops once the chance of still not having reached the
+/// goal is smaller than this.
+const REMAINING_CUTOFF: f64 = 1e-10;
+
+/// The calculation also stops after this many orbs, in case the goal is so
+/// unlikely that it would take too long to get there.
+const MAX_ORBS: usize = 1_000_000;
+
+/// Pity counts more than this many rate increases past the highest rate are
+/// all treated as this one. Reaching them at all takes dozens of summons in a
+/// row without a 5* unit while at the highest rate.
+const PITY_CAP_EXTRA_INCREASES: u32 = 20;
+
+/// The most copies that the goal can need. The work grows quickly with the
+/// number of copies, and the calculation runs on the page, so more than this
+/// would leave it unresponsive for too long.
+const MAX_COPIES: u32 = 5;
+
+/// What opening a stone gives, as far as the goal and the pity are concerned.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Stone {
+    /// A copy of a unit that the goal needs.
+    Hit,
+    /// Any other focus unit.
+    Focus,
+    /// An off-focus 5* unit.
+    Fivestar,
+    /// Anything else.
+    Other,
+}
+
+const STONES: [Stone; 4] = [Stone::Hit, Stone::Focus, Stone::Fivestar, Stone::Other];
+
+/// The possible results of a summoning circle from one state.
+#[derive(Debug, Default)]
+struct Transitions {
+    /// The chance of reaching the goal, indexed by the number of stones opened.
+    finished: [f64; 6],
+    /// The ways that the circle can end without reaching the goal.
+    continued: Vec<Continuation>,
+}
+
+/// A way for a circle to end without reaching the goal.
+#[derive(Copy, Clone, Debug)]
+struct Continuation {
+    chosen_count: u32,
+    got_focus: bool,
+    nonfocus_count: u32,
+    hits: u32,
+    probability: f64,
+}
+
+/// Checks whether the exact calculation can handle the given settings. It needs
+/// a goal for up to `MAX_COPIES` copies of a single unit or for any one of
+/// several units, and a banner
+/// without anything that depends on more than the pity count, like sparks or
+/// free summons.
+pub fn is_supported(banner: &Banner, goal: &Goal, strategy: &Strategy) -> bool {
+    goal_targets(banner, goal).is_some()
+        && !banner.spark
+        && !banner.has_focus_charges()
+        && !banner.free_first_summon
+        && banner.tickets == 0
+        && !banner.daily_free_summon
+        && banner.hard_pity.is_none()
+        && !matches!(strategy.stones, StoneChoice::Script(_))
+}
+
+/// Finds the chance of each focus unit of a color being one that the goal
+/// needs, and the number of those copies needed, if the goal is simple enough.
+fn goal_targets(banner: &Banner, goal: &Goal) -> Option<([f64; 4], u32)> {
+    let goal = goal.as_custom(banner);
+    if goal.goals.is_empty() || goal.goals.iter().any(|part| part.pool != Pool::Focus) {
+        return None;
+    }
+    match goal.kind {
+        GoalKind::Any | GoalKind::All => {}
+        _ => return None,
+    }
+    let copies = if goal.goals.len() == 1 {
+        goal.goals[0].num_copies as u32
+    } else if goal.kind == GoalKind::Any && goal.goals.iter().all(|part| part.num_copies == 1) {
+        1
+    } else {
+        return None;
+    };
+    if copies == 0 || copies > MAX_COPIES {
+        return None;
+    }
+    let mut hit_chances = [0.0; 4];
+    for (color, hit_chance) in hit_chances.iter_mut().enumerate() {
+        let focus_count = banner.focus_sizes[color].max(0) as usize;
+        let needed = goal
+            .goals
+            .iter()
+            .filter(|part| part.unit_color as usize == color)
+            .count()
+            .min(focus_count);
+        if needed > 0 {
+            *hit_chance = needed as f64 / focus_count as f64;
+        }
+    }
+    Some((hit_chances, copies))
+}
+
+/// Calculates the distribution of orbs spent to reach the goal, scaled so that
+/// it looks like a very large number of simulated runs. Returns `None` if the
+/// settings aren't supported.
+pub fn distribution(banner: &Banner, goal: &Goal, strategy: &Strategy) -> Option<Counter> {
+    if !is_supported(banner, goal, strategy) {
+        return None;
+    }
+    let (hit_chances, copies) = goal_targets(banner, goal)?;
+    // Strategies without a script always have a single rule.
+    let mut wanted = strategy.rules().ok()?.first()?.extra_colors;
+    for (wanted, &hit_chance) in wanted.iter_mut().zip(hit_chances.iter()) {
+        *wanted |= hit_chance > 0.0;
+    }
+
+    let sim = Sim::new(banner.clone(), goal.clone(), strategy.clone(), 0);
+    let max_pity_incr = banner.max_pity_increases();
+    let circles: Vec<Vec<(Vec<Stone>, f64)>> = (0..=max_pity_incr)
+        .map(|pity_incr| circle_outcomes(&sim, pity_incr, hit_chances, wanted))
+        .collect();
+    // Indexed by the copies still needed, then the number of rate increases.
+    let transitions: Vec<Vec<Transitions>> = (1..=copies)
+        .map(|needed| {
+            circles
+                .iter()
+                .map(|outcomes| transitions(outcomes, needed))
+                .collect()
+        })
+        .collect();
+    let pity_interval = banner.pity_interval.max(1) as u32;
+    let pity_cap = ((max_pity_incr + PITY_CAP_EXTRA_INCREASES) * pity_interval)
+        .max(banner.starting_pity as u32);
+    let partial_reset = banner.partial_reset_summons();
+    let pity_states = pity_cap as usize + 1;
+    let state_index =
+        |pity_count: u32, needed: u32| (needed as usize - 1) * pity_states + pity_count as usize;
+
+    // Chance of being in each state at the start of a circle, for each number of
+    // orbs spent so far. A circle costs at most 20 orbs, so only that many
+    // levels past the current one are needed at once.
+    const LEVELS: usize = 21;
+    let mut levels = vec![vec![0.0; pity_states * copies as usize]; LEVELS];
+    levels[0][state_index(banner.starting_pity as u32, copies)] = 1.0;
+    let mut finished: Vec<f64> = vec![];
+    let mut remaining = 1.0;
+    let mut orbs = 0;
+    while remaining > REMAINING_CUTOFF && orbs < MAX_ORBS {
+        let level = std::mem::take(&mut levels[orbs % LEVELS]);
+        for (idx, &chance) in level.iter().enumerate() {
+            if chance <= 0.0 {
+                continue;
+            }
+            let pity_count = (idx % pity_states) as u32;
+            let needed = (idx / pity_states) as u32 + 1;
+            let pity_incr = (pity_count / pity_interval).min(max_pity_incr) as usize;
+            let transitions = &transitions[needed as usize - 1][pity_incr];
+            for (chosen_count, &probability) in transitions.finished.iter().enumerate() {
+                if probability > 0.0 {
+                    let total = orbs + orb_cost(chosen_count as u32) as usize;
+                    if total >= finished.len() {
+                        finished.resize(total + 1, 0.0);
+                    }
+                    finished[total] += chance * probability;
+                    remaining -= chance * probability;
+                }
+            }
+            for continuation in &transitions.continued {
+                let pity_count = banner.pity_reset.apply(
+                    pity_count + continuation.chosen_count,
+                    partial_reset,
+                    continuation.got_focus,
+                    continuation.nonfocus_count,
+                );
+                let next = state_index(pity_count.min(pity_cap), needed - continuation.hits);
+                let cost = orb_cost(continuation.chosen_count) as usize;
+                levels[(orbs + cost) % LEVELS][next] += chance * continuation.probability;
+            }
+        }
+        let mut level = level;
+        for chance in level.iter_mut() {
+            *chance = 0.0;
+        }
+        levels[orbs % LEVELS] = level;
+        orbs += 1;
+    }
+
+    let mut data = Counter::default();
+    for (orbs, &chance) in finished.iter().enumerate() {
+        let count = (chance * TOTAL_COUNT).round() as u32;
+        if count > 0 {
+            data[orbs as u32] = count;
+        }
+    }
+    Some(data)
+}
+
+/// Finds the chance of each sequence of stones being opened in a circle with
+/// the given number of rate increases.
+fn circle_outcomes(
+    sim: &Sim,
+    pity_incr: u32,
+    hit_chances: [f64; 4],
+    wanted: [bool; 4],
+) -> Vec<(Vec<Stone>, f64)> {
+    let probabilities = sim.probabilities(pity_incr);
+    let total: f32 = probabilities.iter().sum();
+    // Chance of a single stone being each kind, depending on whether the
+    // strategy would open it.
+    let mut wanted_stones = [0.0; 4];
+    let mut unwanted_stones = [0.0; 4];
+    for (pool, &probability) in probabilities.iter().enumerate() {
+        let pool_chance = (probability / total) as f64;
+        let sizes = sim.tables.pool_sizes[pool];
+        let pool_size: u32 = sizes.iter().map(|&size| size as u32).sum();
+        for color in 0..4 {
+            // An empty pool is sampled as if every color were equally likely.
+            let color_chance = if pool_size == 0 {
+                0.25
+            } else {
+                sizes[color] as f64 / pool_size as f64
+            };
+            let chance = pool_chance * color_chance;
+            let stones = if wanted[color] {
+                &mut wanted_stones
+            } else {
+                &mut unwanted_stones
+            };
+            match Pool::try_from(pool as u8).unwrap() {
+                Pool::Focus => {
+                    let hit = chance * hit_chances[color];
+                    stones[Stone::Hit as usize] += hit;
+                    stones[Stone::Focus as usize] += chance - hit;
+                }
+                Pool::Fivestar => stones[Stone::Fivestar as usize] += chance,
+                _ => stones[Stone::Other as usize] += chance,
+            }
+        }
+    }
+    let unwanted_total: f64 = unwanted_stones.iter().sum();
+
+    let mut outcomes: HashMap<Vec<Stone>, f64> = HashMap::new();
+    outcomes.insert(vec![], 1.0);
+    for i in 0..5 {
+        let mut next: HashMap<Vec<Stone>, f64> = HashMap::new();
+        for (opened, chance) in outcomes {
+            for &stone in &STONES {
+                let stone_chance = wanted_stones[stone as usize];
+                if stone_chance > 0.0 {
+                    let mut opened = opened.clone();
+                    opened.push(stone);
+                    *next.entry(opened).or_insert(0.0) += chance * stone_chance;
+                }
+            }
+            if i == 4 && opened.is_empty() {
+                // Every circle requires at least one summon.
+                for &stone in &STONES {
+                    let stone_chance = unwanted_stones[stone as usize];
+                    if stone_chance > 0.0 {
+                        *next.entry(vec![stone]).or_insert(0.0) += chance * stone_chance;
+                    }
+                }
+            } else {
+                *next.entry(opened).or_insert(0.0) += chance * unwanted_total;
+            }
+        }
+        outcomes = next;
+    }
+    outcomes.into_iter().collect()
+}
+
+/// Groups the sequences of stones opened in a circle by how they change the
+/// state when `needed` more copies are needed.
+fn transitions(outcomes: &[(Vec<Stone>, f64)], needed: u32) -> Transitions {
+    let mut transitions = Transitions::default();
+    let mut continued: HashMap<(u32, bool, u32, u32), f64> = HashMap::new();
+    for (opened, chance) in outcomes {
+        let mut hits = 0;
+        let mut got_focus = false;
+        let mut nonfocus_count = 0;
+        let mut reached_goal = false;
+        for (i, &stone) in opened.iter().enumerate() {
+            match stone {
+                Stone::Hit => {
+                    hits += 1;
+                    got_focus = true;
+                }
+                Stone::Focus => got_focus = true,
+                Stone::Fivestar => nonfocus_count += 1,
+                Stone::Other => {}
+            }
+            if hits == needed {
+                transitions.finished[i + 1] += chance;
+                reached_goal = true;
+                break;
+            }
+        }
+        if !reached_goal {
+            let key = (opened.len() as u32, got_focus, nonfocus_count, hits);
+            *continued.entry(key).or_insert(0.0) += chance;
+        }
+    }
+    transitions.continued = continued
+        .into_iter()
+        .map(
+            |((chosen_count, got_focus, nonfocus_count, hits), probability)| Continuation {
+                chosen_count,
+                got_focus,
+                nonfocus_count,
+                hits,
+                probability,
+            },
+        )
+        .collect();
+    transitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goal::GoalPreset;
+    use crate::stats;
+
+    /// A banner that the exact calculation supports.
+    fn banner() -> Banner {
+        Banner {
+            focus_charges: false,
+            ..Banner::default()
+        }
+    }
+
+    /// Simulates `runs` runs of the same settings with a fixed seed.
+    fn simulate(banner: &Banner, goal: &Goal, strategy: &Strategy, runs: u32) -> Counter {
+        let mut sim = Sim::new(banner.clone(), goal.clone(), strategy.clone(), 1);
+        let mut data = Counter::default();
+        for _ in 0..runs {
+            data[sim.roll_until_goal().orbs] += 1;
+        }
+        data
+    }
+
+    /// Checks that the exact results and a simulation agree, up to the noise
+    /// in the simulation.
+    fn assert_matches_simulation(banner: &Banner, goal: &Goal, strategy: &Strategy) {
+        let exact = distribution(banner, goal, strategy).unwrap();
+        let simulated = simulate(banner, goal, strategy, 20_000);
+        let exact_mean = stats::mean(&exact);
+        let simulated_mean = stats::mean(&simulated);
+        assert!(
+            (exact_mean - simulated_mean).abs() < 0.03 * exact_mean,
+            "exact mean {} vs simulated mean {}",
+            exact_mean,
+            simulated_mean
+        );
+        for &pct in &[0.25, 0.5, 0.9] {
+            let exact = stats::percentile(&exact, pct) as f32;
+            let simulated = stats::percentile(&simulated, pct) as f32;
+            assert!(
+                (exact - simulated).abs() <= 0.05 * exact + 5.0,
+                "{}th percentile: exact {} vs simulated {}",
+                pct * 100.0,
+                exact,
+                simulated
+            );
+        }
+    }
+
+    #[test]
+    fn single_unit_matches_simulation() {
+        let banner = banner();
+        let goal = Goal::Preset(GoalPreset::RedFocus, 1);
+        assert_matches_simulation(&banner, &goal, &Strategy::default());
+    }
+
+    #[test]
+    fn several_copies_match_simulation() {
+        let mut banner = banner();
+        banner.starting_rates = (60, 0);
+        let goal = Goal::Preset(GoalPreset::BlueFocus, 2);
+        assert_matches_simulation(&banner, &goal, &Strategy::default());
+    }
+
+    #[test]
+    fn any_focus_unit_matches_simulation() {
+        let banner = banner();
+        let goal = Goal::Preset(GoalPreset::AnyFocus, 1);
+        let strategy = Strategy {
+            stones: StoneChoice::OpenAll,
+            ..Strategy::default()
+        };
+        assert_matches_simulation(&banner, &goal, &strategy);
+    }
+
+    #[test]
+    fn too_many_copies_are_not_supported() {
+        let banner = banner();
+        let strategy = Strategy::default();
+        let goal = Goal::Preset(GoalPreset::RedFocus, MAX_COPIES as u16);
+        assert!(is_supported(&banner, &goal, &strategy));
+        let goal = Goal::Preset(GoalPreset::RedFocus, MAX_COPIES as u16 + 1);
+        assert!(!is_supported(&banner, &goal, &strategy));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::counter::Counter;
+
+/// Calculates the mean of the data, or 0 if there is no data.
+pub fn mean(data: &Counter) -> f32 {
+    let total: u32 = data.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let weighted_sum: f64 = data
+        .iter()
+        .enumerate()
+        .map(|(value, &count)| value as f64 * count as f64)
+        .sum();
+    (weighted_sum / total as f64) as f32
+}
+
+/// Calculates the standard deviation of the data, or 0 if there are fewer than
+/// two values.
+pub fn standard_deviation(data: &Counter) -> f32 {
+    let total: u32 = data.iter().sum();
+    if total < 2 {
+        return 0.0;
+    }
+    let mean = mean(data) as f64;
+    let squared_deviations: f64 = data
+        .iter()
+        .enumerate()
+        .map(|(value, &count)| (value as f64 - mean).powi(2) * count as f64)
+        .sum();
+    (squared_deviations / (total - 1) as f64).sqrt() as f32
+}
+
+/// The smallest value in the data, if there is any.
+pub fn min(data: &Counter) -> Option<u32> {
+    data.iter()
+        .position(|&count| count > 0)
+        .map(|value| value as u32)
+}
+
+/// The largest value in the data, if there is any.
+pub fn max(data: &Counter) -> Option<u32> {
+    data.iter()
+        .rposition(|&count| count > 0)
+        .map(|value| value as u32)
+}
+
+/// Calculates the given percentile of the data. `pct` is in the range [0.0, 1.0]
+pub fn percentile(data: &Counter, pct: f32) -> u32 {
+    percentiles(data, &[pct])[0]
+}
+
+/// Estimates the standard error of the given percentile of the data, from how
+/// far apart the percentiles one standard error of rank away on either side
+/// are. Returns 0 if there is no data.
+pub fn percentile_standard_error(data: &Counter, pct: f32) -> f32 {
+    let total: u32 = data.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let rank_error = (pct * (1.0 - pct) / total as f32).sqrt();
+    let bounds = percentiles(
+        data,
+        &[(pct - rank_error).max(0.0), (pct + rank_error).min(1.0)],
+    );
+    (bounds[1] - bounds[0]) as f32 / 2.0
+}
+
+/// The fraction of the data that's at or below the given value, or 0 if there
+/// is no data.
+pub fn fraction_at_most(data: &Counter, value: u32) -> f32 {
+    let total: u64 = data.iter().map(|&count| count as u64).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let at_most: u64 = data
+        .iter()
+        .take(value as usize + 1)
+        .map(|&count| count as u64)
+        .sum();
+    (at_most as f64 / total as f64) as f32
+}
+
+/// Estimates the value that the given fraction of the data is at or below,
+/// like `percentile`, but interpolating between the values on either side
+/// instead of rounding up to the next one. Returns 0 if there is no data.
+pub fn interpolated_percentile(data: &Counter, pct: f32) -> f32 {
+    let total: u64 = data.iter().map(|&count| count as u64).sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let target = pct as f64 * total as f64;
+    let mut below = 0;
+    for (value, &count) in data.iter().enumerate() {
+        let through = below + count as u64;
+        if count > 0 && through as f64 >= target {
+            // Counts are spread evenly over the stretch from the previous
+            // value up to this one.
+            let within = (target - below as f64) / count as f64;
+            return (value as f64 - 1.0 + within.max(0.0)).max(0.0) as f32;
+        }
+        below = through;
+    }
+    (data.len() - 1) as f32
+}
+
+/// Gives the range that the given percentile of the whole distribution is in
+/// with 95% confidence, going by how many samples there are. The range spans
+/// the percentiles whose ranks are within the binomial margin of error of
+/// `pct`. Returns (0, 0) if there is no data.
+pub fn percentile_interval(data: &Counter, pct: f32) -> (u32, u32) {
+    let total: u32 = data.iter().sum();
+    if total == 0 {
+        return (0, 0);
+    }
+    let rank_error = 1.96 * (pct * (1.0 - pct) / total as f32).sqrt();
+    let bounds = percentiles(
+        data,
+        &[(pct - rank_error).max(0.0), (pct + rank_error).min(1.0)],
+    );
+    (bounds[0], bounds[1])
+}
+
+/// Calculates multiple percentiles in bulk. `pcts` must be sorted in ascending order
+/// with every value in the range [0.0, 1.0].
+pub fn percentiles(data: &Counter, pcts: &[f32]) -> Vec<u32> {
+    debug_assert!(pcts.iter().all(|x| (0.0..=1.0).contains(x)));
+    debug_assert!((0..pcts.len() - 1).all(|idx| pcts[idx + 1] >= pcts[idx]));
+
+    let total: u32 = data.iter().sum();
+    let mut results = vec![0; pcts.len()];
+
+    if total == 0 {
+        return results;
+    }
+
+    let mut accum_total = 0;
+    let mut out_idx = 0;
+    for value in 0..data.len() as u32 {
+        accum_total += data[value];
+        while out_idx < results.len() && accum_total as f32 / total as f32 > pcts[out_idx] {
+            results[out_idx] = value;
+            out_idx += 1;
+        }
+    }
+
+    if out_idx == results.len() {
+        return results;
+    }
+
+    // The remaining values in pcts are 100% (or close enough for rounding errors)
+    // if it didn't already finish, so they're all the highest value with any
+    // samples. It returned early if all entries were zero, so there is one.
+    let last = (0..data.len() as u32)
+        .rev()
+        .find(|&value| data[value] > 0)
+        .unwrap();
+    for result in &mut results[out_idx..] {
+        *result = last;
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_percentile_is_the_highest_value() {
+        let mut data = Counter::default();
+        for &(orbs, runs) in &[(100, 3), (200, 4), (350, 1)] {
+            data[orbs] = runs;
+        }
+        assert_eq!(percentiles(&data, &[0.5, 1.0]), vec![200, 350]);
+        assert_eq!(percentile_interval(&data, 0.99), (350, 350));
+        assert_eq!(percentile_interval(&data, 1.0), (350, 350));
+    }
+
+    #[test]
+    fn every_run_is_within_the_most_taken() {
+        // The percentage table allows a 100% row, which should be the run
+        // that took the most, with nothing to be uncertain about.
+        let mut data = Counter::default();
+        data[5] = 2;
+        data[20] = 1;
+        let pcts = [0.5, 0.99, 1.0];
+        assert_eq!(percentiles(&data, &pcts), vec![5, 20, 20]);
+        assert_eq!(percentile_interval(&data, 1.0), (20, 20));
+        assert_eq!(Some(percentiles(&data, &[1.0])[0]), max(&data));
+    }
+}
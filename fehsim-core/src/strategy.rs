@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::banner::Banner;
+
+pub mod script;
+use script::Rule;
+
+/// Which stones to open besides the ones that can contribute to the goal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StoneChoice {
+    /// Only open stones of colors that the goal still needs.
+    Snipe,
+    /// Open every stone.
+    OpenAll,
+    /// Also open stones of the selected colors.
+    Custom([bool; 4]),
+    /// Decide which stones to open using a script. See the `script` module.
+    Script(String),
+}
+
+/// The choices that the simulated summoner makes while summoning.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Strategy {
+    pub stones: StoneChoice,
+    /// Whether to stop summoning after claiming a spark, even if the goal
+    /// hasn't been reached yet.
+    pub stop_after_spark: bool,
+}
+
+impl Default for Strategy {
+    fn default() -> Self {
+        Strategy {
+            stones: StoneChoice::Snipe,
+            stop_after_spark: false,
+        }
+    }
+}
+
+impl Strategy {
+    /// The rules that decide which stones get opened besides the ones that
+    /// the goal needs. Every choice is expressed this way, so that the
+    /// simulation only has to handle one kind.
+    pub fn rules(&self) -> Result<Vec<Rule>, String> {
+        let always = |extra_colors| {
+            Ok(vec![Rule {
+                extra_colors,
+                condition: None,
+            }])
+        };
+        match &self.stones {
+            StoneChoice::Snipe => always([false; 4]),
+            StoneChoice::OpenAll => always([true; 4]),
+            StoneChoice::Custom(colors) => always(*colors),
+            StoneChoice::Script(script) => script::parse(script),
+        }
+    }
+
+    /// Checks whether the strategy can be used for simulating.
+    pub fn is_valid(&self) -> bool {
+        self.rules().is_ok()
+    }
+
+    /// A copy of the strategy without the choices that can't change the
+    /// results on the given banner.
+    pub fn results_settings(&self, banner: &Banner) -> Strategy {
+        Strategy {
+            stop_after_spark: self.stop_after_spark && banner.spark,
+            ..self.clone()
+        }
+    }
+
+    /// Parses data from the representation used in query strings to share settings.
+    pub fn from_query_string(s: &str) -> Option<Self> {
+        let data = base64::decode(s).ok()?;
+        bincode::deserialize(&data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both of the settings in the form that results are matched up with.
+    fn results_settings(banner: &Banner, strategy: &Strategy) -> (Banner, Strategy) {
+        (banner.results_settings(), strategy.results_settings(banner))
+    }
+
+    #[test]
+    fn renaming_keeps_the_results() {
+        let mut banner = Banner::default();
+        let strategy = Strategy::default();
+        let before = results_settings(&banner, &strategy);
+
+        banner.name = Some("Legendary Heroes".into());
+        banner.focus_names[0] = vec!["Marth".into()];
+        banner.duration_days = 7;
+        assert_eq!(results_settings(&banner, &strategy), before);
+
+        banner.spark = true;
+        assert_ne!(results_settings(&banner, &strategy), before);
+        banner.spark = false;
+        assert_eq!(results_settings(&banner, &strategy), before);
+    }
+
+    #[test]
+    fn stopping_after_a_spark_only_matters_with_one() {
+        let mut banner = Banner::default();
+        let strategy = Strategy {
+            stop_after_spark: true,
+            ..Strategy::default()
+        };
+        assert_eq!(
+            strategy.results_settings(&banner),
+            Strategy::default().results_settings(&banner)
+        );
+        banner.spark = true;
+        assert_ne!(
+            strategy.results_settings(&banner),
+            Strategy::default().results_settings(&banner)
+        );
+    }
+}
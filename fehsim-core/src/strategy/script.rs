@@ -0,0 +1,251 @@
+//! A small language for describing summoning strategies that change depending
+//! on how the session is going, for example:
+//!
+//! ```text
+//! open all when pity >= 5; open red when spark >= 30; snipe
+//! ```
+//!
+//! A script is a list of rules separated by semicolons or line breaks. At the
+//! start of each summoning circle, the first rule whose condition holds decides
+//! which stones get opened. Stones that can contribute to the goal are always
+//! opened. If no rule applies, only those stones are opened.
+
+use crate::Color;
+
+/// A quantity that a rule's condition can look at.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Variable {
+    /// How much the 5* rates have increased, in percent.
+    Pity,
+    /// The number of summons made so far.
+    Summons,
+    /// The number of orbs spent so far.
+    Orbs,
+    /// The number of summons made towards the next spark.
+    Spark,
+}
+
+/// The ways that a variable can be compared to a value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    Greater,
+}
+
+/// The condition under which a rule applies.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub variable: Variable,
+    pub comparison: Comparison,
+    pub value: f32,
+}
+
+/// A single rule of a script.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rule {
+    /// Colors of stones to open even when the goal doesn't need them.
+    pub extra_colors: [bool; 4],
+    /// When the rule applies. Rules without a condition always apply.
+    pub condition: Option<Condition>,
+}
+
+/// The state of a run at the start of a summoning circle.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CircleState {
+    pub pity_pct: f32,
+    pub summons: u32,
+    pub orbs: u32,
+    pub spark: u32,
+}
+
+impl Condition {
+    /// Checks whether the condition holds in the given state.
+    fn holds(&self, state: &CircleState) -> bool {
+        let actual = match self.variable {
+            Variable::Pity => state.pity_pct,
+            Variable::Summons => state.summons as f32,
+            Variable::Orbs => state.orbs as f32,
+            Variable::Spark => state.spark as f32,
+        };
+        match self.comparison {
+            Comparison::Less => actual < self.value,
+            Comparison::LessEqual => actual <= self.value,
+            Comparison::Equal => (actual - self.value).abs() < 1e-3,
+            Comparison::GreaterEqual => actual >= self.value,
+            Comparison::Greater => actual > self.value,
+        }
+    }
+}
+
+/// Decides which colors of stones to open besides the ones that the goal needs.
+pub fn extra_colors(rules: &[Rule], state: &CircleState) -> [bool; 4] {
+    rules
+        .iter()
+        .find(|rule| rule.condition.iter().all(|cond| cond.holds(state)))
+        .map_or([false; 4], |rule| rule.extra_colors)
+}
+
+/// Parses a script into its rules, or describes the first problem with it.
+pub fn parse(script: &str) -> Result<Vec<Rule>, String> {
+    script
+        .split(&[';', '\n'][..])
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+        .map(parse_rule)
+        .collect()
+}
+
+/// Parses a single rule, like "open red, blue when summons < 40".
+fn parse_rule(rule: &str) -> Result<Rule, String> {
+    let rule = rule.to_lowercase();
+    let (action, condition) = match rule.find(" when ") {
+        Some(idx) => (&rule[..idx], Some(&rule[idx + " when ".len()..])),
+        None => (&rule[..], None),
+    };
+    Ok(Rule {
+        extra_colors: parse_action(action.trim())?,
+        condition: condition
+            .map(|cond| parse_condition(cond.trim()))
+            .transpose()?,
+    })
+}
+
+/// Parses the part of a rule that says which stones to open.
+fn parse_action(action: &str) -> Result<[bool; 4], String> {
+    if action == "snipe" {
+        return Ok([false; 4]);
+    }
+    if action == "open all" {
+        return Ok([true; 4]);
+    }
+    if !action.starts_with("open ") {
+        return Err(format!(
+            "\"{}\" should be \"snipe\", \"open all\", or \"open\" and a list of colors",
+            action
+        ));
+    }
+    let mut colors = [false; 4];
+    for name in action["open ".len()..]
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|name| !name.is_empty())
+    {
+        let color = match name {
+            "red" | "r" => Color::Red,
+            "blue" | "b" => Color::Blue,
+            "green" | "g" => Color::Green,
+            "colorless" | "c" | "gray" | "grey" => Color::Colorless,
+            _ => return Err(format!("Unknown color \"{}\"", name)),
+        };
+        colors[color as usize] = true;
+    }
+    Ok(colors)
+}
+
+/// Parses the part of a rule that says when it applies, like "pity >= 5%".
+fn parse_condition(condition: &str) -> Result<Condition, String> {
+    // Two-character operators come first so that ">=" isn't read as ">".
+    let operators = [
+        (">=", Comparison::GreaterEqual),
+        ("<=", Comparison::LessEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ];
+    let (idx, op, comparison) = operators
+        .iter()
+        .find_map(|&(op, comparison)| condition.find(op).map(|idx| (idx, op, comparison)))
+        .ok_or_else(|| format!("\"{}\" needs a comparison like >= or <", condition))?;
+    let variable = match condition[..idx].trim() {
+        "pity" => Variable::Pity,
+        "summons" => Variable::Summons,
+        "orbs" => Variable::Orbs,
+        "spark" => Variable::Spark,
+        other => {
+            return Err(format!(
+                "Unknown quantity \"{}\", expected pity, summons, orbs, or spark",
+                other
+            ))
+        }
+    };
+    let value_text = condition[idx + op.len()..].trim().trim_end_matches('%');
+    let value = value_text
+        .trim()
+        .parse::<f32>()
+        .map_err(|_| format!("\"{}\" isn't a number", value_text))?;
+    Ok(Condition {
+        variable,
+        comparison,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(pity_pct: f32, spark: u32) -> CircleState {
+        CircleState {
+            pity_pct,
+            spark,
+            ..CircleState::default()
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_applies() {
+        let rules = parse("open all when pity >= 5%; open red, b when spark >= 30\nsnipe").unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(extra_colors(&rules, &state(5.0, 35)), [true; 4]);
+        assert_eq!(
+            extra_colors(&rules, &state(4.5, 35)),
+            [true, true, false, false]
+        );
+        assert_eq!(extra_colors(&rules, &state(4.5, 10)), [false; 4]);
+    }
+
+    #[test]
+    fn nothing_extra_is_opened_without_a_matching_rule() {
+        let rules = parse("Open Colorless when summons < 10;").unwrap();
+        let after = CircleState {
+            summons: 10,
+            ..CircleState::default()
+        };
+        assert_eq!(
+            extra_colors(&rules, &CircleState::default()),
+            [false, false, false, true]
+        );
+        assert_eq!(extra_colors(&rules, &after), [false; 4]);
+        assert_eq!(parse(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn two_character_comparisons_are_read_whole() {
+        let rules = parse("open all when orbs <= 100").unwrap();
+        assert_eq!(
+            rules[0].condition,
+            Some(Condition {
+                variable: Variable::Orbs,
+                comparison: Comparison::LessEqual,
+                value: 100.0,
+            })
+        );
+    }
+
+    #[test]
+    fn mistakes_are_described() {
+        assert!(parse("open purple").unwrap_err().contains("Unknown color"));
+        assert!(parse("summon all").unwrap_err().contains("should be"));
+        assert!(parse("snipe when pity")
+            .unwrap_err()
+            .contains("needs a comparison"));
+        assert!(parse("snipe when luck > 1")
+            .unwrap_err()
+            .contains("Unknown quantity"));
+        assert!(parse("snipe when pity > lots")
+            .unwrap_err()
+            .contains("isn't a number"));
+    }
+}
@@ -0,0 +1,91 @@
+//! A list of heroes that can be summoned, for suggesting focus units by name.
+//! It's compiled in from `units.csv`, which has a line for each hero.
+
+use crate::{Color, Pool};
+
+/// Where a hero can be summoned outside of a focus.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Availability {
+    /// Only on their own banners, like legendary and seasonal heroes.
+    Limited,
+    /// The 5* pool, only at 5*.
+    Fivestar,
+    /// The 5* pool and the 4* special rate.
+    FourstarSpecial,
+    /// The 4* and 3* pools.
+    Regular,
+}
+
+impl Availability {
+    /// The pool that the hero turns up in as a 5* unit outside of a focus, if
+    /// any.
+    pub fn fivestar_pool(self) -> Option<Pool> {
+        match self {
+            Availability::Fivestar | Availability::FourstarSpecial => Some(Pool::Fivestar),
+            Availability::Limited | Availability::Regular => None,
+        }
+    }
+}
+
+/// A hero that can be summoned.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Unit {
+    pub name: &'static str,
+    pub color: Color,
+    pub availability: Availability,
+}
+
+const DATA: &str = include_str!("units.csv");
+
+/// Every hero in the list, in the order that they're listed.
+pub fn all() -> Vec<Unit> {
+    DATA.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        // The first line names the columns.
+        .skip(1)
+        .map(|line| parse_line(line).unwrap_or_else(|| panic!("Invalid unit: {}", line)))
+        .collect()
+}
+
+/// The heroes of the given color, in the order that they're listed.
+pub fn of_color(color: Color) -> Vec<Unit> {
+    all()
+        .into_iter()
+        .filter(|unit| unit.color == color)
+        .collect()
+}
+
+/// Looks up a hero by name, ignoring case.
+pub fn find(name: &str) -> Option<Unit> {
+    all()
+        .into_iter()
+        .find(|unit| unit.name.eq_ignore_ascii_case(name.trim()))
+}
+
+fn parse_line(line: &'static str) -> Option<Unit> {
+    let mut fields = line.split(',').map(str::trim);
+    let name = fields.next()?;
+    let color = match fields.next()? {
+        "red" => Color::Red,
+        "blue" => Color::Blue,
+        "green" => Color::Green,
+        "colorless" => Color::Colorless,
+        _ => return None,
+    };
+    let availability = match fields.next()? {
+        "limited" => Availability::Limited,
+        "fivestar" => Availability::Fivestar,
+        "special" => Availability::FourstarSpecial,
+        "regular" => Availability::Regular,
+        _ => return None,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(Unit {
+        name,
+        color,
+        availability,
+    })
+}
@@ -0,0 +1,111 @@
+use rand::{distributions::Distribution, Rng};
+
+/// Optimized version of rand::WeightedIndex for a fixed-size collection of floats,
+/// using Walker's alias method so that every sample takes constant time.
+#[derive(Copy, Clone, Debug)]
+pub struct WeightedIndex<const N: usize> {
+    // Each entry is picked with probability `probs[i]` when its slot is chosen,
+    // and otherwise the slot gives `aliases[i]` instead.
+    probs: [f32; N],
+    aliases: [u8; N],
+}
+
+/// Sampler for the four colors.
+pub type WeightedIndex4 = WeightedIndex<4>;
+
+/// Sampler for the six summoning pools.
+pub type WeightedIndex6 = WeightedIndex<6>;
+
+impl<const N: usize> Default for WeightedIndex<N> {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+impl<const N: usize> WeightedIndex<N> {
+    /// Constructs a sampler from the given weights. Weights do not need to sum to 1.
+    /// If every weight is zero, all entries are equally likely.
+    pub fn new<T: Into<f32> + Copy>(values: [T; N]) -> Self {
+        let total: f32 = values.iter().map(|&value| value.into()).sum();
+        if total <= 0.0 {
+            return Self::uniform();
+        }
+
+        let mut scaled = [0.0; N];
+        for (scaled, &value) in scaled.iter_mut().zip(values.iter()) {
+            *scaled = value.into() * N as f32 / total;
+        }
+
+        // Fixed-size stacks of the entries that are below and above average.
+        let mut small = [0; N];
+        let mut small_len = 0;
+        let mut large = [0; N];
+        let mut large_len = 0;
+        for (i, &value) in scaled.iter().enumerate() {
+            if value < 1.0 {
+                small[small_len] = i;
+                small_len += 1;
+            } else {
+                large[large_len] = i;
+                large_len += 1;
+            }
+        }
+
+        let mut table = Self::uniform();
+        while small_len > 0 && large_len > 0 {
+            small_len -= 1;
+            let less = small[small_len];
+            let more = large[large_len - 1];
+            table.probs[less] = scaled[less];
+            table.aliases[less] = more as u8;
+            // The above-average entry gives up part of its weight to fill the slot.
+            scaled[more] -= 1.0 - scaled[less];
+            if scaled[more] < 1.0 {
+                large_len -= 1;
+                small[small_len] = more;
+                small_len += 1;
+            }
+        }
+        // Rounding errors can leave entries without a partner. Ones with any
+        // weight are within rounding error of exactly filling their own slot,
+        // which `uniform` already represents, but ones without any weight
+        // must never be picked.
+        let mut heaviest = 0;
+        for i in 1..N {
+            if scaled[i] > scaled[heaviest] {
+                heaviest = i;
+            }
+        }
+        for &less in &small[..small_len] {
+            if scaled[less] <= 0.0 {
+                table.probs[less] = 0.0;
+                table.aliases[less] = heaviest as u8;
+            }
+        }
+        table
+    }
+
+    /// A sampler where every entry is equally likely.
+    fn uniform() -> Self {
+        let mut aliases = [0; N];
+        for (i, alias) in aliases.iter_mut().enumerate() {
+            *alias = i as u8;
+        }
+        Self {
+            probs: [1.0; N],
+            aliases,
+        }
+    }
+}
+
+impl<const N: usize> Distribution<usize> for WeightedIndex<N> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let choice = rng.gen::<f32>() * N as f32;
+        let slot = (choice as usize).min(N - 1);
+        if choice - (slot as f32) < self.probs[slot] {
+            slot
+        } else {
+            self.aliases[slot] as usize
+        }
+    }
+}
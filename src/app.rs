@@ -0,0 +1,2061 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::future::Future;
+use std::hash::Hasher;
+
+use seed::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use wasm_bindgen_futures::JsFuture;
+
+use fehsim_core::banner::{Banner, BannerType, PityReset, PoolSizes};
+use fehsim_core::catalog::CatalogEntry;
+use fehsim_core::counter::Counter;
+use fehsim_core::goal::{Goal, GoalKind, GoalPart, GoalPreset};
+use fehsim_core::migrate;
+use fehsim_core::sim::{exact, Sim};
+use fehsim_core::strategy::{StoneChoice, Strategy};
+use fehsim_core::{Color, Pool};
+
+use crate::catalog::{self, Catalog};
+use crate::money::{OrbPack, OrbPrices};
+use crate::plan::{self, Plan};
+use crate::results::{self, Comparison, Measure, PinnedCurve, Progress, SideStats, StopRule};
+use crate::scenarios::{self, SavedResults, Scenario};
+use crate::worker::{self, Runner};
+use crate::{banner, export, goal, issues, query_string, storage, strategy, subpages};
+
+// Model
+
+/// The current page that the application is on.
+#[derive(Copy, Clone, Debug)]
+pub enum Page {
+    Main,
+    Help,
+    Changelog,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Page::Main
+    }
+}
+
+/// How long to wait after the last change to the settings before running
+/// automatically, in milliseconds.
+const AUTO_RUN_DELAY: i32 = 300;
+
+/// The number of runs to simulate each time when going for a set number of
+/// them, if the user hasn't entered one.
+const DEFAULT_RUN_COUNT: u32 = 100_000;
+
+/// How long to simulate for each time the "Run" button is pressed. Longer runs
+/// give more precise results, especially for the rarer outcomes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
+pub enum Precision {
+    Fast,
+    Normal,
+    High,
+    /// A set number of runs, however long they take, so that the results are
+    /// equally precise on every device.
+    Runs,
+    /// As many runs as it takes for the results to converge.
+    Converged,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision::Normal
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Precision::Fast => "Fast (0.2s)",
+            Precision::Normal => "Normal (0.5s)",
+            Precision::High => "High (2s)",
+            Precision::Runs => "Set number of runs",
+            Precision::Converged => "Until converged",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Precision {
+    /// When to stop simulating, given the number of runs that the user has
+    /// entered, if any.
+    pub fn stop_rule(self, run_count: Option<u32>) -> StopRule {
+        match self {
+            Precision::Fast => StopRule::Time(200.0),
+            Precision::Normal => StopRule::Time(500.0),
+            Precision::High => StopRule::Time(2000.0),
+            Precision::Runs => StopRule::Runs(run_count.unwrap_or(DEFAULT_RUN_COUNT).max(1)),
+            Precision::Converged => StopRule::Converged,
+        }
+    }
+}
+
+/// A saved set of settings that the current ones are compared against.
+#[derive(Clone, Debug)]
+struct Baseline {
+    banner: Banner,
+    /// The saved goal, or `None` to use whatever the current goal is, so that
+    /// only the banners are compared.
+    goal: Option<Goal>,
+    strategy: Strategy,
+}
+
+impl Baseline {
+    /// The goal that the saved settings are simulated with.
+    fn goal<'a>(&'a self, current: &'a Goal) -> &'a Goal {
+        self.goal.as_ref().unwrap_or(current)
+    }
+
+    /// Whether the saved settings can be simulated along with the current
+    /// goal.
+    fn is_usable(&self, current: &Goal) -> bool {
+        self.goal(current).is_available(&self.banner)
+    }
+}
+
+/// Settings to replace all at once, like the ones from a permalink. Whatever
+/// isn't given stays as it is.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    pub banner: Option<Banner>,
+    pub goal: Option<Goal>,
+    pub strategy: Option<Strategy>,
+    pub seed: Option<u64>,
+    /// Whether to start running once the settings are in place.
+    pub run: bool,
+}
+
+impl Settings {
+    /// Whether there's nothing to change.
+    fn is_empty(&self) -> bool {
+        self.banner.is_none()
+            && self.goal.is_none()
+            && self.strategy.is_none()
+            && self.seed.is_none()
+            && !self.run
+    }
+}
+
+/// The settings as they're remembered between visits, so that reloading the
+/// page doesn't lose them. The banners and goals are saved like in
+/// permalinks, so that they can still be read after the format changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct SavedSettings {
+    version: u32,
+    banner: String,
+    goal: String,
+    strategy: String,
+    /// The earlier steps of the plan, and its budget, if there is one.
+    plan: Option<(Vec<(String, String)>, u32)>,
+    seed: Option<u64>,
+    exact: bool,
+    precision: Precision,
+    run_count: Option<u32>,
+    auto_run: bool,
+}
+
+impl SavedSettings {
+    fn new(model: &Model) -> Self {
+        SavedSettings {
+            version: migrate::VERSION,
+            banner: scenarios::encode(&model.banner),
+            goal: scenarios::encode(&model.goal),
+            strategy: scenarios::encode(&model.strategy),
+            plan: model.plan.as_ref().map(|plan| {
+                let steps = plan
+                    .steps
+                    .iter()
+                    .map(|(banner, goal)| (scenarios::encode(banner), scenarios::encode(goal)))
+                    .collect();
+                (steps, plan.budget)
+            }),
+            seed: model.seed,
+            exact: model.exact,
+            precision: model.precision,
+            run_count: model.run_count,
+            auto_run: model.auto_run,
+        }
+    }
+
+    /// Puts the saved settings into the model, unless they were saved in a
+    /// version of the format that can't be read anymore.
+    fn restore(&self, model: &mut Model) {
+        let (banner, goal, strategy) = match scenarios::decode_settings(
+            self.version,
+            &self.banner,
+            &self.goal,
+            &self.strategy,
+        ) {
+            Some(settings) => settings,
+            None => return,
+        };
+        let plan = match &self.plan {
+            Some((steps, budget)) => {
+                let steps = steps
+                    .iter()
+                    .map(|(banner, goal)| {
+                        Some((
+                            migrate::banner(banner.as_str(), self.version)?,
+                            migrate::goal(goal.as_str(), self.version)?,
+                        ))
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match steps {
+                    Some(steps) => Some(Plan {
+                        steps,
+                        budget: *budget,
+                    }),
+                    None => return,
+                }
+            }
+            None => None,
+        };
+        model.banner = banner;
+        model.goal = goal;
+        model.strategy = strategy;
+        model.plan = plan;
+        model.seed = self.seed;
+        model.exact = self.exact;
+        model.precision = self.precision;
+        model.run_count = self.run_count;
+        model.auto_run = self.auto_run;
+    }
+}
+
+/// Everything that's remembered in the browser, for moving it to another one
+/// or keeping it safe.
+#[derive(Debug, Serialize, Deserialize)]
+struct Backup {
+    settings: SavedSettings,
+    scenarios: Vec<Scenario>,
+    percentiles: Vec<f32>,
+    orb_prices: OrbPrices,
+}
+
+/// Results put aside when the settings changed, to bring back if the settings
+/// change back.
+#[derive(Debug)]
+struct CachedResults {
+    /// Identifies the settings that the results are for. See
+    /// `Model::settings_key`.
+    key: u64,
+    data: Counter,
+    side_stats: SideStats,
+    comparison: Comparison,
+    run_seed: Option<u64>,
+    converged: bool,
+}
+
+/// The most results kept aside at once. Putting aside more throws away the
+/// oldest ones.
+const RESULTS_CACHE_SIZE: usize = 5;
+
+/// The most results that can be pinned to the chart at once. Pinning more
+/// replaces the oldest ones.
+const MAX_PINNED_CURVES: usize = 5;
+
+/// Data model for the app.
+#[derive(Default, Debug)]
+struct Model {
+    /// The data that the simulation has gathered so far.
+    pub data: Counter,
+    /// Statistics about the units obtained along the way in each run so far.
+    pub side_stats: SideStats,
+    /// The parameters of the current banner.
+    pub banner: Banner,
+    /// The paremeters of the current goal.
+    pub goal: Goal,
+    /// The choices made while summoning.
+    pub strategy: Strategy,
+    /// The seed for the random number generator chosen by the user, if any.
+    pub seed: Option<u64>,
+    /// The seed that the current results were generated with, if they all
+    /// came from the same one.
+    pub run_seed: Option<u64>,
+    /// Identifies the settings that the current results are for, once there
+    /// are some. See `settings_key`.
+    pub results_key: Option<u64>,
+    /// Whether the simulation that the runner has is the one that generated
+    /// the current results, so that more runs can pick up where it left off.
+    pub resumable: bool,
+    /// Results for earlier settings, with the most recent last.
+    pub results_cache: Vec<CachedResults>,
+    /// Whether the settings have changed since the current results were
+    /// simulated, in a way that changes what the results would be. They stay
+    /// until the next run, for reference.
+    pub stale: bool,
+    /// Whether to calculate the results exactly instead of simulating, when
+    /// the settings allow it.
+    pub exact: bool,
+    /// How long to simulate for each time.
+    pub precision: Precision,
+    /// Whether to run automatically whenever the settings change.
+    pub auto_run: bool,
+    /// The number of runs to simulate each time when going for a set number
+    /// of them, if the user has entered one.
+    pub run_count: Option<u32>,
+    /// Where the simulation runs, set up the first time it's needed. It keeps
+    /// the simulation that generated the current results around so that
+    /// pressing "More" continues from where it left off, which makes the
+    /// results reproducible from the seed.
+    pub runner: Option<Runner>,
+    /// Identifies the current results to the worker. Changes whenever they're
+    /// thrown away, so that runs of the old settings can be ignored.
+    pub run_id: u32,
+    /// Whether the simulation is in the middle of running.
+    pub running: bool,
+    /// Whether the exact results are being calculated.
+    pub calculating: bool,
+    /// Whether the current results were simulated until they converged.
+    pub converged: bool,
+    /// When the current run started, for keeping track of how long it's taken.
+    pub run_start: f64,
+    /// How far along the current run is.
+    pub progress: Progress,
+    /// Whether runs have been asked for and haven't come back yet.
+    pub awaiting_runs: bool,
+    /// Settings saved for comparing against, if any.
+    pub baseline: Option<Baseline>,
+    /// Banners to summon on before the current one, if any.
+    pub plan: Option<Plan>,
+    /// The banners running in the game, once they've been loaded.
+    pub catalog: Catalog,
+    /// The issues with the settings that were last dismissed, which stay
+    /// hidden until they change.
+    pub dismissed_issues: Vec<String>,
+    /// How the current results compare to the saved settings.
+    pub comparison: Comparison,
+    /// The current page that the application is on.
+    pub curr_page: Page,
+    /// The point on the graph that the user has chose to highlight.
+    pub graph_highlight: Option<f32>,
+    /// The percentiles to show in the results, sorted.
+    pub percentiles: Vec<f32>,
+    /// The chance of success to look up the orbs needed for, if one's been
+    /// entered.
+    pub lookup_chance: Option<f32>,
+    /// The number of orbs to look up the chance of success for, which is also
+    /// marked on the chart, if one's been entered.
+    pub lookup_orbs: Option<u32>,
+    /// The number of orbs that the goal actually took the user, to compare
+    /// against the results, if one's been entered.
+    pub luck_orbs: Option<u32>,
+    /// How many orbs each bar of the histogram covers, if it's been chosen.
+    pub histogram_bin_width: Option<u32>,
+    /// Where the pointer is over the chance of success chart, as a fraction
+    /// of its width.
+    pub cdf_hover: Option<f32>,
+    /// Whether the charts spread out the orbs on a log scale.
+    pub log_scale: bool,
+    /// What the results are counted in.
+    pub measure: Measure,
+    /// The prices of orb packs, for estimating what the results cost.
+    pub orb_prices: OrbPrices,
+    /// Earlier results shown on the chance of success chart along with the
+    /// current ones.
+    pub pinned_curves: Vec<PinnedCurve>,
+    /// Whether a summary of the current results has been copied.
+    pub summary_copied: bool,
+    /// Settings saved under a name for later.
+    pub scenarios: Vec<Scenario>,
+    /// The name to save the current settings under.
+    pub scenario_name: String,
+    /// The settings as they were last saved for the next visit.
+    pub saved_settings: Option<SavedSettings>,
+    /// Whether to save the current results along with the settings.
+    pub scenario_with_results: bool,
+}
+
+impl Model {
+    /// Whatever has to be changed before the settings can be run.
+    fn issues(&self) -> Vec<String> {
+        let mut issues = self.banner.problems();
+        if !self.goal.is_available(&self.banner) {
+            let problems = self.goal.problems(&self.banner);
+            if problems.is_empty() {
+                issues.push("The goal can't be reached on this banner.".into());
+            } else {
+                issues.extend(problems);
+            }
+        }
+        if let Err(message) = self.strategy.rules() {
+            issues.push(format!("Strategy: {}", message));
+        }
+        issues
+    }
+
+    /// Whether the settings can be run, as opposed to ones that need to be
+    /// changed first.
+    fn is_runnable(&self) -> bool {
+        self.issues().is_empty()
+    }
+
+    /// Identifies everything that the results depend on, so that results can
+    /// be matched up with the settings they came from. Settings that can't
+    /// change the results, like the banner's name, are left out, since
+    /// changing them keeps the current results.
+    fn settings_key(&self) -> u64 {
+        let settings = (
+            self.banner.results_settings(),
+            &self.goal,
+            self.strategy.results_settings(&self.banner),
+            self.baseline.as_ref().map(|baseline| {
+                (
+                    baseline.banner.results_settings(),
+                    &baseline.goal,
+                    baseline.strategy.results_settings(&baseline.banner),
+                )
+            }),
+            self.plan.as_ref().map(|plan| {
+                let steps: Vec<_> = plan
+                    .steps
+                    .iter()
+                    .map(|(banner, goal)| (banner.results_settings(), goal))
+                    .collect();
+                (steps, plan.budget)
+            }),
+            self.seed,
+            self.exact_available(),
+        );
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&bincode::serialize(&settings).unwrap());
+        hasher.finish()
+    }
+
+    /// Throws away all of the data that the simulation has gathered, putting
+    /// it aside in the cache in case the settings it's for come back.
+    fn clear_data(&mut self) {
+        if let Some(key) = self.results_key.take() {
+            if !self.data.is_empty() {
+                self.results_cache.retain(|cached| cached.key != key);
+                if self.results_cache.len() >= RESULTS_CACHE_SIZE {
+                    self.results_cache.remove(0);
+                }
+                self.results_cache.push(CachedResults {
+                    key,
+                    data: std::mem::take(&mut self.data),
+                    side_stats: std::mem::take(&mut self.side_stats),
+                    comparison: std::mem::take(&mut self.comparison),
+                    run_seed: self.run_seed,
+                    converged: self.converged,
+                });
+            }
+        }
+        self.data.clear();
+        self.side_stats.clear();
+        self.comparison.clear();
+        self.run_seed = None;
+        self.resumable = false;
+        self.stale = false;
+        self.run_id = self.run_id.wrapping_add(1);
+        self.running = false;
+        self.awaiting_runs = false;
+        self.calculating = false;
+        self.converged = false;
+        self.summary_copied = false;
+    }
+
+    /// Stops simulating, since the settings have changed. The results are
+    /// kept, and `update` sorts out whether they still match the settings.
+    fn settings_changed(&mut self) {
+        self.run_id = self.run_id.wrapping_add(1);
+        self.running = false;
+        self.calculating = false;
+        self.awaiting_runs = false;
+        self.resumable = false;
+    }
+
+    /// Matches the results up with the current settings after they've
+    /// changed: the results stay current if the change doesn't affect them,
+    /// results put aside for the new settings are brought back, and otherwise
+    /// the results are marked as stale.
+    fn match_results_to_settings(&mut self) {
+        let key = self.settings_key();
+        if self.results_key == Some(key) {
+            self.stale = false;
+            return;
+        }
+        if let Some(index) = self
+            .results_cache
+            .iter()
+            .position(|cached| cached.key == key)
+        {
+            // Take the entry out before `clear_data` stashes the current
+            // results, since that can shift the rest of the cache.
+            let cached = self.results_cache.remove(index);
+            self.clear_data();
+            self.data = cached.data;
+            self.side_stats = cached.side_stats;
+            self.comparison = cached.comparison;
+            self.run_seed = cached.run_seed;
+            self.converged = cached.converged;
+            self.results_key = Some(key);
+        } else {
+            self.stale = !self.data.is_empty();
+        }
+    }
+
+    /// Whether there are results for the current settings.
+    fn has_current_results(&self) -> bool {
+        !self.data.is_empty() && !self.stale
+    }
+
+    /// Whether the results are going to be calculated exactly.
+    fn exact_available(&self) -> bool {
+        self.exact
+            && self.plan.is_none()
+            && exact::is_supported(&self.banner, &self.goal, &self.strategy)
+    }
+}
+
+// Update
+
+/// Event definition for the app.
+#[derive(Clone, Debug)]
+pub enum Msg {
+    /// Does nothing, not even re-render the page. Exists only to satisfy
+    /// static typing in some situations.
+    Null,
+    /// Holds a collection of messages that will all be queued up at once.
+    Multiple(Vec<Msg>),
+    /// Display an alert
+    Alert { message: String },
+    /// Gather data.
+    Run,
+    /// Simulate for a little while on the page, when the worker isn't
+    /// available.
+    RunChunk { run_id: u32 },
+    /// Add runs that have finished, and ask for more if there's time left.
+    RunProgress(worker::Response),
+    /// The worker couldn't run the simulation, so it has to run on the page.
+    RunnerFailed,
+    /// Stop gathering data, keeping what's been gathered so far.
+    CancelRun,
+    /// Throw away the data gathered so far, without changing any settings.
+    ResultsClear,
+    /// Change whether to run automatically whenever the settings change.
+    AutoRunToggle,
+    /// Run automatically, if the settings haven't changed again since this
+    /// was scheduled.
+    AutoRun { run_id: u32 },
+    /// Change the number of focus units for a given color.
+    BannerFocusSizeChange { color: Color, quantity: i8 },
+    /// Change the names of the focus units of a color, which also sets how
+    /// many there are.
+    BannerFocusNamesChange { color: Color, names: Vec<String> },
+    /// Change the number of 4* focus units for a given color.
+    BannerFourstarFocusSizeChange { color: Color, quantity: i8 },
+    /// Change the rate of the 4* focus pool.
+    BannerFourstarFocusRateChange { rate: u8 },
+    /// Change the starting focus and off-focus 5* rates, in tenths of a
+    /// percent.
+    BannerRateChange { rates: (u16, u16) },
+    /// Change the number of units of each color in the pools outside of the
+    /// focus.
+    BannerPoolSizesChange { sizes: PoolSizes },
+    /// Change the regular 4* and 3* rates, in tenths of a percent.
+    BannerLowerRatesChange { rates: (u16, u16) },
+    /// Change the name of the banner.
+    BannerNameChange { name: String },
+    /// Set up the banner as one of the usual kinds of banners.
+    BannerTypeChange { banner_type: BannerType },
+    /// Change whether the banner uses focus charges.
+    BannerFocusChargesToggle,
+    /// Change whether the banner has a spark.
+    BannerSparkToggle,
+    /// Change whether the banner has a 4* special rate.
+    BannerFourstarSpecialToggle,
+    /// Change whether the first summon on the banner is free.
+    BannerFreeFirstSummonToggle,
+    /// Change how much the 5* rate increases each time, in tenths of a percent.
+    BannerPityStepChange { step: u8 },
+    /// Change how many summons it takes for the 5* rate to increase.
+    BannerPityIntervalChange { interval: u8 },
+    /// Change the number of summons without a focus unit before one is
+    /// guaranteed.
+    BannerHardPityChange { threshold: Option<u16> },
+    /// Change how the 5* rate increase is undone.
+    BannerPityResetChange { reset: PityReset },
+    /// Change the number of summoning tickets available.
+    BannerTicketsChange { tickets: u16 },
+    /// Change whether the banner gives a free summon every day.
+    BannerDailyFreeSummonToggle,
+    /// Change how many days the banner lasts.
+    BannerDurationChange { days: u16 },
+    /// Change the number of summons already made without a 5* unit.
+    BannerStartingPityChange { summons: u16 },
+    /// Change the number of summons already made towards the spark.
+    BannerStartingSparkChange { summons: u16 },
+    /// Load the banners running in the game.
+    CatalogLoad,
+    /// The banners running in the game have been loaded, or couldn't be.
+    CatalogFetched {
+        entries: Result<Vec<CatalogEntry>, String>,
+    },
+    /// Set the banner to one of the banners running in the game.
+    CatalogPick { index: usize },
+    /// Set the goal to a certain preset.
+    GoalPresetChange { preset: GoalPreset },
+    /// Set the number of copies to use for the preset.
+    GoalPresetQuantityChange { quantity: u16 },
+    /// Change the current preset into a custom goal.
+    GoalMakeCustom,
+    /// Change the color for an individual unit target.
+    GoalPartColorChange { index: usize, color: Color },
+    /// Change which pool an individual unit target is summoned from.
+    GoalPartPoolChange { index: usize, pool: Pool },
+    /// Change the number of copies for an individual unit target.
+    GoalPartQuantityChange { index: usize, quantity: u16 },
+    /// Add a new individual unit target.
+    GoalPartAdd { color: Color, quantity: u16 },
+    /// Change whether the individual targets all need to happen or just one.
+    GoalKindChange { kind: GoalKind },
+    /// Change which stones get opened.
+    StrategyStonesChange { stones: StoneChoice },
+    /// Change whether stones of a color are opened when the goal doesn't need them.
+    StrategyExtraColorToggle { color: Color },
+    /// Change the script that decides which stones get opened.
+    StrategyScriptChange { script: String },
+    /// Change whether to stop summoning after claiming a spark.
+    StrategyStopAfterSparkToggle,
+    /// Replace several settings at once.
+    SettingsSet { settings: Settings },
+    /// Hide the current list of issues with the settings.
+    IssuesDismiss,
+    /// Change the seed for the random number generator.
+    SeedChange { seed: Option<u64> },
+    /// Change whether the results are calculated exactly when possible.
+    ExactToggle,
+    /// Change how long to simulate for each time.
+    PrecisionChange { precision: Precision },
+    /// Change the number of runs to simulate each time.
+    RunCountChange { count: Option<u32> },
+    /// Save the current settings to compare other ones against. Without the
+    /// goal, the saved banner is always compared using the current goal.
+    BaselineSave { with_goal: bool },
+    /// Stop comparing against saved settings.
+    BaselineClear,
+    /// Save the current banner and goal as a step of the plan, to be summoned
+    /// on before the next one.
+    PlanAddStep,
+    /// Take a step out of the plan.
+    PlanRemoveStep { index: usize },
+    /// Change the orbs available for the whole plan.
+    PlanBudgetChange { orbs: u32 },
+    /// Change which page of the application is open.
+    PageChange(Page),
+    /// Generate a permalink that saves the application's paremeters.
+    Permalink,
+    /// Highlight a point on the graph.
+    GraphHighlight { frac: f32 },
+    /// Download the results as a CSV file.
+    ExportCsv,
+    /// Download the chart with the given id as an SVG file.
+    ChartDownload { chart: &'static str },
+    /// Put a summary of the results on the clipboard.
+    SummaryCopy,
+    /// The summary of the results has been put on the clipboard, or couldn't
+    /// be.
+    SummaryCopied { copied: bool },
+    /// Download the results and the settings they came from as a JSON file.
+    ExportJson,
+    /// Read results that were exported as JSON from a file.
+    ResultsImport { file: web_sys::File },
+    /// The file to import results from has been read, or couldn't be.
+    ResultsImported { text: Result<String, String> },
+    /// Show another percentile in the results.
+    PercentileAdd { pct: f32 },
+    /// Stop showing one of the percentiles in the results.
+    PercentileRemove { index: usize },
+    /// Go back to showing the usual percentiles.
+    PercentilesReset,
+    /// Change the chance of success to look up the orbs needed for.
+    LookupChanceChange { chance: Option<f32> },
+    /// Change the number of orbs to look up the chance of success for.
+    LookupOrbsChange { orbs: Option<u32> },
+    /// Change the number of orbs that the goal actually took.
+    LuckOrbsChange { orbs: Option<u32> },
+    /// Change how many orbs each bar of the histogram covers.
+    HistogramBinWidthChange { width: Option<u32> },
+    /// Show the chance of success at a point on the chart, or stop showing it.
+    CdfHover { frac: Option<f32> },
+    /// Change whether the charts use a log scale for the orbs.
+    LogScaleToggle,
+    /// Change what the results are counted in.
+    MeasureChange { measure: Measure },
+    /// Change what the orb prices are written in.
+    OrbCurrencyChange { currency: String },
+    /// Change how many orbs come in an orb pack.
+    OrbPackOrbsChange { index: usize, orbs: u32 },
+    /// Change the price of an orb pack.
+    OrbPackPriceChange { index: usize, price: f32 },
+    /// Add another orb pack.
+    OrbPackAdd,
+    /// Remove an orb pack.
+    OrbPackRemove { index: usize },
+    /// Go back to the usual orb pack prices.
+    OrbPricesReset,
+    /// Keep the current results on the chance of success chart.
+    CurvePin,
+    /// Take pinned results off of the chance of success chart.
+    CurveUnpin { index: usize },
+    /// Change the name to save the current settings under.
+    ScenarioNameChange { name: String },
+    /// Change whether the current results are saved along with the settings.
+    ScenarioWithResultsToggle,
+    /// Save the current settings under the chosen name, replacing any
+    /// scenario with the same name.
+    ScenarioSave,
+    /// Replace the current settings with a saved scenario.
+    ScenarioLoad { index: usize },
+    /// Delete a saved scenario.
+    ScenarioDelete { index: usize },
+    /// Download everything that's remembered in the browser as a JSON file.
+    BackupExport,
+    /// Read a backup from a file.
+    BackupImport { file: web_sys::File },
+    /// The backup has been read from the file, or couldn't be.
+    BackupImported { text: Result<String, String> },
+}
+
+/// Update model with the given message, and schedule a run if the settings
+/// changed and auto-run is on.
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    let run_id = model.run_id;
+    let settings_changed = !matches!(msg, Msg::ResultsClear);
+    let auto_run = model.auto_run && settings_changed;
+    // Settings that don't invalidate the run, and loaded results that bring
+    // their own settings, are the only changes that keep the same run id.
+    let saves_settings = matches!(
+        msg,
+        Msg::PrecisionChange { .. }
+            | Msg::RunCountChange { .. }
+            | Msg::AutoRunToggle
+            | Msg::ScenarioLoad { .. }
+            | Msg::ResultsImported { .. }
+    );
+    apply_msg(msg, model, orders);
+    if settings_changed && model.run_id != run_id {
+        model.match_results_to_settings();
+    }
+    if auto_run && model.run_id != run_id {
+        orders.perform_cmd(after_delay(
+            AUTO_RUN_DELAY,
+            Msg::AutoRun {
+                run_id: model.run_id,
+            },
+        ));
+    }
+    if saves_settings || model.run_id != run_id {
+        let settings = SavedSettings::new(model);
+        if model.saved_settings.as_ref() != Some(&settings) {
+            storage::save(storage::SETTINGS_KEY, &settings);
+            model.saved_settings = Some(settings);
+        }
+    }
+}
+
+/// Update model with the given message.
+fn apply_msg(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    match msg {
+        Msg::Null => {
+            orders.skip();
+        }
+        Msg::Multiple(messages) => {
+            orders.skip();
+            for msg in messages {
+                orders.send_msg(msg);
+            }
+        }
+        Msg::Alert { message } => alert(&message),
+        Msg::BannerFocusSizeChange { color, quantity } => {
+            model.banner.set_focus_size(color, quantity);
+            model.settings_changed();
+        }
+        Msg::BannerFocusNamesChange { color, names } => {
+            let size = model.banner.focus_sizes[color as usize];
+            model.banner.set_focus_names(color, names);
+            // Only the number of units changes the results.
+            if model.banner.focus_sizes[color as usize] != size {
+                model.settings_changed();
+            }
+        }
+        Msg::BannerRateChange { rates } => {
+            // The 4* and 3* rates follow along unless they've been changed.
+            if model.banner.lower_rates == Banner::usual_lower_rates(model.banner.starting_rates) {
+                model.banner.lower_rates = Banner::usual_lower_rates(rates);
+            }
+            model.banner.starting_rates = rates;
+            model.settings_changed();
+        }
+        Msg::BannerPoolSizesChange { mut sizes } => {
+            // Exclusive units are a part of the 5* pool.
+            for (exclusive, &total) in sizes.fivestar_exclusive.iter_mut().zip(&sizes.fivestar) {
+                *exclusive = (*exclusive).min(total);
+            }
+            model.banner.pool_sizes = sizes;
+            model.settings_changed();
+        }
+        Msg::BannerLowerRatesChange { rates } => {
+            model.banner.lower_rates = rates;
+            model.settings_changed();
+        }
+        Msg::BannerNameChange { name } => {
+            // The name doesn't change the results, so they're kept.
+            model.banner.name = if name.trim().is_empty() {
+                None
+            } else {
+                Some(name)
+            };
+        }
+        Msg::BannerTypeChange { banner_type } => {
+            banner_type.apply(&mut model.banner);
+            model.settings_changed();
+        }
+        Msg::BannerFourstarFocusSizeChange { color, quantity } => {
+            model.banner.fourstar_focus_sizes[color as usize] = quantity;
+            model.settings_changed();
+        }
+        Msg::BannerFourstarFocusRateChange { rate } => {
+            model.banner.fourstar_focus_rate = rate;
+            model.settings_changed();
+        }
+        Msg::BannerFocusChargesToggle => {
+            model.banner.focus_charges = !model.banner.focus_charges;
+            model.settings_changed();
+        }
+        Msg::BannerSparkToggle => {
+            model.banner.spark = !model.banner.spark;
+            model.settings_changed();
+        }
+        Msg::BannerFourstarSpecialToggle => {
+            model.banner.fourstar_special = !model.banner.fourstar_special;
+            model.settings_changed();
+        }
+        Msg::BannerPityStepChange { step } => {
+            model.banner.pity_step = step;
+            model.settings_changed();
+        }
+        Msg::BannerPityIntervalChange { interval } => {
+            model.banner.pity_interval = interval.max(1);
+            model.settings_changed();
+        }
+        Msg::BannerHardPityChange { threshold } => {
+            model.banner.hard_pity = threshold;
+            model.settings_changed();
+        }
+        Msg::BannerPityResetChange { reset } => {
+            model.banner.pity_reset = reset;
+            model.settings_changed();
+        }
+        Msg::BannerFreeFirstSummonToggle => {
+            model.banner.free_first_summon = !model.banner.free_first_summon;
+            model.settings_changed();
+        }
+        Msg::BannerTicketsChange { tickets } => {
+            model.banner.tickets = tickets;
+            model.settings_changed();
+        }
+        Msg::BannerDailyFreeSummonToggle => {
+            model.banner.daily_free_summon = !model.banner.daily_free_summon;
+            model.settings_changed();
+        }
+        Msg::BannerDurationChange { days } => {
+            model.banner.duration_days = days;
+            if model.banner.daily_free_summon {
+                model.settings_changed();
+            }
+        }
+        Msg::BannerStartingPityChange { summons } => {
+            model.banner.starting_pity = summons;
+            model.settings_changed();
+        }
+        Msg::BannerStartingSparkChange { summons } => {
+            model.banner.starting_spark = summons % 40;
+            model.settings_changed();
+        }
+        Msg::CatalogLoad => {
+            model.catalog = Catalog::Loading;
+            orders.perform_cmd(catalog::fetch_catalog());
+        }
+        Msg::CatalogFetched { entries } => {
+            model.catalog = match entries {
+                Ok(entries) => Catalog::Loaded(entries),
+                Err(reason) => Catalog::Failed(reason),
+            };
+        }
+        Msg::CatalogPick { index } => {
+            if let Catalog::Loaded(entries) = &model.catalog {
+                if let Some(entry) = entries.get(index) {
+                    model.banner = entry.banner(catalog::today());
+                    model.settings_changed();
+                }
+            }
+        }
+        Msg::Run => {
+            if !model.is_runnable() {
+                return;
+            }
+            if model.stale {
+                model.clear_data();
+            }
+            if model.running || model.calculating {
+                return;
+            }
+            if model.exact_available() {
+                // The results are about to be calculated again, so there's no
+                // need to keep the old ones.
+                model.results_key = None;
+                model.clear_data();
+                model.results_key = Some(model.settings_key());
+                model.calculating = true;
+                model.graph_highlight = None;
+                let request = worker::Request::Exact {
+                    run_id: model.run_id,
+                    banner: model.banner.clone(),
+                    goal: model.goal.clone(),
+                    strategy: model.strategy.clone(),
+                };
+                request_runs(model, request, orders);
+                return;
+            }
+            model.running = true;
+            model.run_start = js_sys::Date::now();
+            model.progress = Progress {
+                runs: 0,
+                elapsed: 0.0,
+                convergence: results::convergence(&model.data),
+                stop_rule: model.precision.stop_rule(model.run_count),
+            };
+            model.graph_highlight = None;
+            if model.awaiting_runs {
+                // Runs from before the last time it was stopped are still on
+                // their way, and will pick up from there.
+                return;
+            }
+            let request = if model.resumable {
+                worker::Request::Continue {
+                    run_id: model.run_id,
+                    max_runs: model.progress.runs_left(),
+                }
+            } else {
+                // Results that were brought back from the cache or a file are
+                // added to with a new simulation, which can't repeat the runs
+                // they already have by reusing their seed.
+                let seed = match model.seed {
+                    Some(seed) if model.data.is_empty() => seed,
+                    _ => Sim::random_seed(),
+                };
+                model.run_seed = if model.data.is_empty() {
+                    Some(seed)
+                } else {
+                    None
+                };
+                model.results_key = Some(model.settings_key());
+                model.resumable = true;
+                worker::Request::Start {
+                    run_id: model.run_id,
+                    banner: model.banner.clone(),
+                    goal: model.goal.clone(),
+                    strategy: model.strategy.clone(),
+                    baseline: model
+                        .baseline
+                        .as_ref()
+                        .filter(|baseline| baseline.is_usable(&model.goal))
+                        .map(|baseline| {
+                            (
+                                baseline.banner.clone(),
+                                baseline.goal(&model.goal).clone(),
+                                baseline.strategy.clone(),
+                            )
+                        }),
+                    plan: model
+                        .plan
+                        .as_ref()
+                        .map(|plan| (plan.steps.clone(), plan.budget)),
+                    seed,
+                    max_runs: model.progress.runs_left(),
+                }
+            };
+            request_runs(model, request, orders);
+        }
+        Msg::RunChunk { run_id } => {
+            orders.skip();
+            if run_id != model.run_id {
+                return;
+            }
+            if !model.running && !model.calculating {
+                model.awaiting_runs = false;
+                return;
+            }
+            if let Some(Runner::Local(simulator)) = model.runner.as_mut() {
+                let response =
+                    simulator.run_for(run_id, worker::CHUNK_TIME, model.progress.runs_left());
+                orders.send_msg(Msg::RunProgress(response));
+            }
+        }
+        Msg::RunnerFailed => {
+            model.runner = Some(Runner::Local(worker::Simulator::default()));
+            // The worker's simulation is gone, so whatever it was asked for
+            // starts over on the page.
+            model.resumable = false;
+            if model.awaiting_runs && (model.running || model.calculating) {
+                model.awaiting_runs = false;
+                model.running = false;
+                model.calculating = false;
+                orders.send_msg(Msg::Run);
+            }
+        }
+        Msg::RunProgress(response) => {
+            if response.run_id != model.run_id {
+                orders.skip();
+                return;
+            }
+            model.awaiting_runs = false;
+            if response.exact {
+                model.calculating = false;
+                model.data = response.data;
+                return;
+            }
+            model.data.merge(&response.data);
+            model.side_stats.merge(&response.side_stats);
+            model.comparison.merge(&response.comparison);
+            if model.running {
+                model.progress.runs += response.side_stats.runs(&response.data);
+                model.progress.elapsed = js_sys::Date::now() - model.run_start;
+                if let StopRule::Converged = model.progress.stop_rule {
+                    model.progress.convergence = results::convergence(&model.data);
+                    model.converged = model.progress.is_finished();
+                }
+                if !model.progress.is_finished() {
+                    let request = worker::Request::Continue {
+                        run_id: model.run_id,
+                        max_runs: model.progress.runs_left(),
+                    };
+                    request_runs(model, request, orders);
+                } else {
+                    model.running = false;
+                }
+            }
+        }
+        Msg::CancelRun => {
+            if model.calculating {
+                // The calculation can't be interrupted, so its results are
+                // ignored when they come back instead.
+                model.settings_changed();
+            }
+            model.running = false;
+        }
+        Msg::ResultsClear => {
+            // Thrown away for good, instead of being put aside.
+            model.results_key = None;
+            model.clear_data();
+            model.graph_highlight = None;
+        }
+        Msg::AutoRunToggle => {
+            model.auto_run = !model.auto_run;
+            if model.auto_run && (model.data.is_empty() || model.stale) {
+                orders.send_msg(Msg::Run);
+            }
+        }
+        Msg::AutoRun { run_id } => {
+            orders.skip();
+            // Any change since this was scheduled has scheduled another one.
+            if model.auto_run && run_id == model.run_id && (model.data.is_empty() || model.stale) {
+                orders.send_msg(Msg::Run);
+            }
+        }
+        Msg::GoalPresetChange { preset } => {
+            let count = if let Goal::Preset(_, count) = model.goal {
+                count
+            } else {
+                1
+            };
+            if preset.is_available(&model.banner) {
+                model.goal = Goal::Preset(preset, count);
+                model.settings_changed();
+            }
+        }
+        Msg::GoalPresetQuantityChange { quantity } => {
+            if let Goal::Preset(_, count) = &mut model.goal {
+                *count = quantity;
+                model.settings_changed();
+            }
+        }
+        Msg::GoalPartColorChange { index, color } => {
+            if let Goal::Custom(custom_goal) = &mut model.goal {
+                custom_goal.goals[index].unit_color = color;
+                custom_goal.merge_duplicates(&model.banner);
+                model.settings_changed();
+            }
+        }
+        Msg::GoalPartPoolChange { index, pool } => {
+            if let Goal::Custom(custom_goal) = &mut model.goal {
+                custom_goal.goals[index].pool = pool;
+                custom_goal.merge_duplicates(&model.banner);
+                model.settings_changed();
+            }
+        }
+        Msg::GoalMakeCustom => {
+            let mut custom = model.goal.as_custom(&model.banner);
+            // 4* focuses in custom goals are not supported
+            for part in &mut custom.goals {
+                if part.pool == Pool::FourstarFocus {
+                    part.pool = Pool::Focus;
+                }
+            }
+            model.goal = Goal::Custom(custom);
+            model.settings_changed();
+        }
+        Msg::GoalPartQuantityChange { index, quantity } => {
+            if let Goal::Custom(custom_goal) = &mut model.goal {
+                if quantity == 0 {
+                    custom_goal.goals.remove(index);
+                } else {
+                    custom_goal.goals[index].num_copies = quantity;
+                }
+                model.settings_changed();
+            }
+        }
+        Msg::GoalPartAdd { color, quantity } => {
+            if let Goal::Custom(custom_goal) = &mut model.goal {
+                custom_goal.goals.push(GoalPart {
+                    unit_color: color,
+                    num_copies: quantity,
+                    pool: Pool::Focus,
+                });
+                custom_goal.merge_duplicates(&model.banner);
+                model.settings_changed();
+            }
+        }
+        Msg::GoalKindChange { kind } => {
+            if let Goal::Custom(custom_goal) = &mut model.goal {
+                custom_goal.kind = kind;
+                custom_goal.merge_duplicates(&model.banner);
+                model.settings_changed();
+            }
+        }
+        Msg::StrategyStonesChange { stones } => {
+            model.strategy.stones = stones;
+            model.settings_changed();
+        }
+        Msg::StrategyExtraColorToggle { color } => {
+            if let StoneChoice::Custom(colors) = &mut model.strategy.stones {
+                colors[color as usize] = !colors[color as usize];
+                model.settings_changed();
+            }
+        }
+        Msg::StrategyScriptChange { script } => {
+            if let StoneChoice::Script(current) = &mut model.strategy.stones {
+                *current = script;
+                model.settings_changed();
+            }
+        }
+        Msg::StrategyStopAfterSparkToggle => {
+            model.strategy.stop_after_spark = !model.strategy.stop_after_spark;
+            if model.banner.spark {
+                model.settings_changed();
+            }
+        }
+        Msg::SettingsSet { settings } => {
+            // Everything is replaced before anything is checked, so that a
+            // banner is never judged against the goal that it's replacing.
+            if let Some(banner) = settings.banner {
+                model.banner = banner;
+            }
+            if let Some(goal) = settings.goal {
+                model.goal = goal;
+            }
+            if let Some(strategy) = settings.strategy {
+                model.strategy = strategy;
+            }
+            if settings.seed.is_some() {
+                model.seed = settings.seed;
+            }
+            model.settings_changed();
+            if settings.run && model.is_runnable() {
+                orders.send_msg(Msg::Run);
+            }
+        }
+        Msg::IssuesDismiss => {
+            model.dismissed_issues = model.issues();
+        }
+        Msg::SeedChange { seed } => {
+            model.seed = seed;
+            model.settings_changed();
+        }
+        Msg::ExactToggle => {
+            model.exact = !model.exact;
+            model.settings_changed();
+        }
+        Msg::PrecisionChange { precision } => {
+            model.precision = precision;
+        }
+        Msg::RunCountChange { count } => {
+            model.run_count = count;
+        }
+        Msg::BaselineSave { with_goal } => {
+            if model.is_runnable() {
+                model.baseline = Some(Baseline {
+                    banner: model.banner.clone(),
+                    goal: if with_goal {
+                        Some(model.goal.clone())
+                    } else {
+                        None
+                    },
+                    strategy: model.strategy.clone(),
+                });
+                model.settings_changed();
+            }
+        }
+        Msg::BaselineClear => {
+            model.baseline = None;
+            model.settings_changed();
+        }
+        Msg::PlanAddStep => {
+            if model.goal.is_available(&model.banner) {
+                let step = (model.banner.clone(), model.goal.clone());
+                let budget = model.goal.budget().unwrap_or(goal::DEFAULT_BUDGET);
+                model
+                    .plan
+                    .get_or_insert(Plan {
+                        steps: vec![],
+                        budget,
+                    })
+                    .steps
+                    .push(step);
+                model.settings_changed();
+            }
+        }
+        Msg::PlanRemoveStep { index } => {
+            if let Some(plan) = &mut model.plan {
+                if index < plan.steps.len() {
+                    plan.steps.remove(index);
+                }
+                if plan.steps.is_empty() {
+                    model.plan = None;
+                }
+                model.settings_changed();
+            }
+        }
+        Msg::PlanBudgetChange { orbs } => {
+            if let Some(plan) = &mut model.plan {
+                plan.budget = orbs;
+                model.settings_changed();
+            }
+        }
+        Msg::PageChange(page) => {
+            model.curr_page = page;
+        }
+        Msg::Permalink => {
+            let mut search = format!(
+                "v={}&banner={}&goal={}&strategy={}&run=1",
+                migrate::VERSION,
+                base64::encode(&bincode::serialize(&model.banner).unwrap()),
+                base64::encode(&bincode::serialize(&model.goal).unwrap()),
+                base64::encode(&bincode::serialize(&model.strategy).unwrap())
+            );
+            if let Some(seed) = model.run_seed.or(model.seed) {
+                search.push_str(&format!("&seed={}", seed));
+            }
+            let url = seed::Url::new(vec![""]).search(&search);
+            seed::push_route(url);
+        }
+        Msg::GraphHighlight { frac } => {
+            model.graph_highlight = Some(frac);
+        }
+        Msg::ExportCsv => {
+            if model.stale {
+                return;
+            }
+            orders.skip();
+            let csv = export::results_csv(&model.data, &model.banner, &model.goal, model.run_seed);
+            if export::download("feh-sim-results.csv", "text/csv", &csv).is_err() {
+                alert("The results couldn't be saved.");
+            }
+        }
+        Msg::ChartDownload { chart } => {
+            orders.skip();
+            let saved = export::chart_svg(chart).map(|svg| {
+                export::download(&format!("feh-sim-{}.svg", chart), "image/svg+xml", &svg)
+            });
+            if !matches!(saved, Some(Ok(()))) {
+                alert("The chart couldn't be saved.");
+            }
+        }
+        Msg::SummaryCopy => {
+            if model.stale {
+                return;
+            }
+            let (shown, measure) =
+                results::shown_data(&model.data, &model.side_stats, model.measure);
+            let summary = export::summary_markdown(
+                shown,
+                measure.unit(),
+                &model.banner,
+                &model.goal,
+                &model.percentiles,
+            );
+            orders.skip().perform_cmd(async move {
+                Ok::<Msg, Msg>(Msg::SummaryCopied {
+                    copied: export::copy_to_clipboard(&summary).await.is_ok(),
+                })
+            });
+        }
+        Msg::SummaryCopied { copied } => {
+            if copied {
+                model.summary_copied = true;
+            } else {
+                alert("The summary couldn't be copied.");
+            }
+        }
+        Msg::ExportJson => {
+            if model.stale {
+                return;
+            }
+            orders.skip();
+            let file = export::ResultsFile {
+                version: migrate::VERSION,
+                banner: model.banner.clone(),
+                goal: model.goal.clone(),
+                strategy: model.strategy.clone(),
+                seed: model.run_seed,
+                runs: model.side_stats.runs(&model.data) as u64,
+                data: model.data.clone(),
+                side_stats: model.side_stats.clone(),
+            };
+            if export::download("feh-sim-results.json", "application/json", &file.to_json())
+                .is_err()
+            {
+                alert("The results couldn't be saved.");
+            }
+        }
+        Msg::ResultsImport { file } => {
+            orders
+                .skip()
+                .perform_cmd(export::read_file(file, |text| Msg::ResultsImported {
+                    text,
+                }));
+        }
+        Msg::ResultsImported { text } => {
+            match text.and_then(|text| export::ResultsFile::from_json(&text)) {
+                Ok(file) => {
+                    // The results only make sense for the settings they came
+                    // from, so those replace the current ones along with
+                    // anything that would change what the results mean.
+                    model.banner = file.banner;
+                    model.goal = file.goal;
+                    model.strategy = file.strategy;
+                    model.seed = file.seed;
+                    model.plan = None;
+                    model.clear_data();
+                    model.data = file.data;
+                    model.side_stats = file.side_stats;
+                    model.run_seed = file.seed;
+                    model.results_key = Some(model.settings_key());
+                    model.graph_highlight = None;
+                }
+                Err(message) => alert(&message),
+            }
+        }
+        Msg::PercentileAdd { pct } => {
+            if !model.percentiles.contains(&pct) {
+                model.percentiles.push(pct);
+                model.percentiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                storage::save(storage::PERCENTILES_KEY, &model.percentiles);
+            }
+        }
+        Msg::PercentileRemove { index } => {
+            if index < model.percentiles.len() {
+                model.percentiles.remove(index);
+                storage::save(storage::PERCENTILES_KEY, &model.percentiles);
+            }
+        }
+        Msg::PercentilesReset => {
+            model.percentiles = results::DEFAULT_PERCENTILES.to_vec();
+            storage::save(storage::PERCENTILES_KEY, &model.percentiles);
+        }
+        Msg::LookupChanceChange { chance } => {
+            model.lookup_chance = chance;
+        }
+        Msg::LookupOrbsChange { orbs } => {
+            model.lookup_orbs = orbs;
+        }
+        Msg::LuckOrbsChange { orbs } => {
+            model.luck_orbs = orbs;
+        }
+        Msg::HistogramBinWidthChange { width } => {
+            model.histogram_bin_width = width;
+        }
+        Msg::CdfHover { frac } => {
+            model.cdf_hover = frac;
+        }
+        Msg::LogScaleToggle => {
+            model.log_scale = !model.log_scale;
+        }
+        Msg::MeasureChange { measure } => {
+            model.measure = measure;
+            model.graph_highlight = None;
+        }
+        Msg::OrbCurrencyChange { currency } => {
+            model.orb_prices.currency = currency;
+            storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+        }
+        Msg::OrbPackOrbsChange { index, orbs } => {
+            if let Some(pack) = model.orb_prices.packs.get_mut(index) {
+                pack.orbs = orbs;
+                storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+            }
+        }
+        Msg::OrbPackPriceChange { index, price } => {
+            if let Some(pack) = model.orb_prices.packs.get_mut(index) {
+                pack.price = price;
+                storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+            }
+        }
+        Msg::OrbPackAdd => {
+            model.orb_prices.packs.push(OrbPack {
+                orbs: 1,
+                price: 0.0,
+            });
+            storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+        }
+        Msg::OrbPackRemove { index } => {
+            if index < model.orb_prices.packs.len() {
+                model.orb_prices.packs.remove(index);
+                storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+            }
+        }
+        Msg::OrbPricesReset => {
+            model.orb_prices = OrbPrices::default();
+            storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+        }
+        Msg::CurvePin => {
+            if !model.data.is_empty() && !model.stale {
+                let goal = plan::goal_label(&model.goal);
+                let label = match &model.banner.name {
+                    Some(name) => format!("{}, {}", name, goal),
+                    None => goal,
+                };
+                if model.pinned_curves.len() >= MAX_PINNED_CURVES {
+                    model.pinned_curves.remove(0);
+                }
+                model.pinned_curves.push(PinnedCurve {
+                    label,
+                    data: model.data.clone(),
+                    summons: model.side_stats.summons.clone(),
+                });
+            }
+        }
+        Msg::CurveUnpin { index } => {
+            if index < model.pinned_curves.len() {
+                model.pinned_curves.remove(index);
+            }
+        }
+        Msg::ScenarioNameChange { name } => {
+            model.scenario_name = name;
+        }
+        Msg::ScenarioWithResultsToggle => {
+            model.scenario_with_results = !model.scenario_with_results;
+        }
+        Msg::ScenarioSave => {
+            let name = model.scenario_name.trim().to_string();
+            if name.is_empty() {
+                return;
+            }
+            let results = if model.scenario_with_results && model.has_current_results() {
+                Some(SavedResults {
+                    data: model.data.clone(),
+                    side_stats: model.side_stats.clone(),
+                    run_seed: model.run_seed,
+                    converged: model.converged,
+                })
+            } else {
+                None
+            };
+            let scenario =
+                Scenario::new(name, &model.banner, &model.goal, &model.strategy, results);
+            match model
+                .scenarios
+                .iter_mut()
+                .find(|saved| saved.name == scenario.name)
+            {
+                Some(saved) => *saved = scenario,
+                None => model.scenarios.push(scenario),
+            }
+            storage::save(storage::SCENARIOS_KEY, &model.scenarios);
+        }
+        Msg::ScenarioLoad { index } => {
+            let scenario = match model.scenarios.get(index) {
+                Some(scenario) => scenario.clone(),
+                None => return,
+            };
+            let (banner, goal, strategy) = match scenario.settings() {
+                Some(settings) => settings,
+                None => return,
+            };
+            // Scenarios are for a single banner, so any plan is left behind.
+            model.banner = banner;
+            model.goal = goal;
+            model.strategy = strategy;
+            model.plan = None;
+            match scenario.results {
+                Some(results) => {
+                    model.clear_data();
+                    model.data = results.data;
+                    model.side_stats = results.side_stats;
+                    model.run_seed = results.run_seed;
+                    model.converged = results.converged;
+                    model.results_key = Some(model.settings_key());
+                    model.graph_highlight = None;
+                }
+                None => model.settings_changed(),
+            }
+        }
+        Msg::ScenarioDelete { index } => {
+            if index < model.scenarios.len() {
+                model.scenarios.remove(index);
+                storage::save(storage::SCENARIOS_KEY, &model.scenarios);
+            }
+        }
+        Msg::BackupExport => {
+            orders.skip();
+            let backup = Backup {
+                settings: SavedSettings::new(model),
+                scenarios: model.scenarios.clone(),
+                percentiles: model.percentiles.clone(),
+                orb_prices: model.orb_prices.clone(),
+            };
+            let json = serde_json::to_string_pretty(&backup).unwrap();
+            if export::download("feh-sim-backup.json", "application/json", &json).is_err() {
+                alert("The backup couldn't be saved.");
+            }
+        }
+        Msg::BackupImport { file } => {
+            orders
+                .skip()
+                .perform_cmd(export::read_file(file, |text| Msg::BackupImported { text }));
+        }
+        Msg::BackupImported { text } => {
+            let backup = match text {
+                Ok(text) => match serde_json::from_str::<Backup>(&text) {
+                    Ok(backup) => backup,
+                    Err(_) => {
+                        alert("That isn't a backup file.");
+                        return;
+                    }
+                },
+                Err(message) => {
+                    alert(&message);
+                    return;
+                }
+            };
+            let confirmed = seed::window()
+                .confirm_with_message(
+                    "Restoring the backup replaces the current settings and all of the saved scenarios. Restore it?",
+                )
+                .unwrap_or(false);
+            if !confirmed {
+                return;
+            }
+            backup.settings.restore(model);
+            model.scenarios = backup.scenarios;
+            if backup
+                .percentiles
+                .iter()
+                .all(|&pct| pct > 0.0 && pct <= 1.0)
+            {
+                model.percentiles = backup.percentiles;
+            }
+            model.orb_prices = backup.orb_prices;
+            storage::save(storage::SCENARIOS_KEY, &model.scenarios);
+            storage::save(storage::PERCENTILES_KEY, &model.percentiles);
+            storage::save(storage::ORB_PRICES_KEY, &model.orb_prices);
+            model.settings_changed();
+        }
+    }
+}
+
+/// Sends the message after the given number of milliseconds.
+fn after_delay(delay: i32, msg: Msg) -> impl Future<Output = Result<Msg, Msg>> {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        seed::window()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, delay)
+            .unwrap();
+    });
+    async move {
+        let _ = JsFuture::from(promise).await;
+        Ok(msg)
+    }
+}
+
+/// Asks for more runs, either from the worker or by simulating on the page
+/// once it has been updated. Starts up the runner the first time.
+fn request_runs(model: &mut Model, request: worker::Request, orders: &mut impl Orders<Msg>) {
+    if model.runner.is_none() {
+        let (app, msg_mapper) = (orders.clone_app(), orders.msg_mapper());
+        let (failed_app, failed_mapper) = (orders.clone_app(), orders.msg_mapper());
+        model.runner = Some(Runner::new(
+            move |response| app.update(msg_mapper(Msg::RunProgress(response))),
+            move || failed_app.update(failed_mapper(Msg::RunnerFailed)),
+        ));
+    }
+    let run_id = model.run_id;
+    let runner = model.runner.as_mut().unwrap();
+    runner.send(request);
+    if let Runner::Local(_) = runner {
+        orders.after_next_render(move |_| Msg::RunChunk { run_id });
+    }
+    model.awaiting_runs = true;
+}
+
+// View
+
+/// Display the current state.
+fn view(model: &Model) -> Vec<Node<Msg>> {
+    match model.curr_page {
+        Page::Main => main_page(model),
+        Page::Help => subpages::help(),
+        Page::Changelog => subpages::changelog(),
+    }
+}
+
+/// Display the main page of the application.
+fn main_page(model: &Model) -> Vec<Node<Msg>> {
+    let goal = model.goal.as_custom(&model.banner);
+    let issues = model.issues();
+    vec![
+        header![
+            class!["no-select"],
+            a![
+                "How to use",
+                attrs! [
+                    At::Href => "/help";
+                ],
+            ],
+            " | v0.3.1 ",
+            a![
+                "Changelog",
+                attrs![
+                    At::Href => "/changelog";
+                ],
+            ],
+            " | ",
+            a![
+                "Contact",
+                attrs![
+                    At::Href => "https://www.reddit.com/message/compose?to=minno&subject=fehstatsim%20site%20help";
+                ]
+            ]
+        ],
+        div![
+            class!["no-select"],
+            id!["content"],
+            match &model.banner.name {
+                Some(name) => h2![name],
+                None => seed::empty(),
+            },
+            goal::goal_selector(&model.goal, &model.banner),
+            catalog::catalog_selector(&model.catalog),
+            scenarios::scenario_manager(
+                &model.scenarios,
+                &model.scenario_name,
+                model.scenario_with_results,
+                model.has_current_results(),
+            ),
+            banner::banner_selector(&model.banner),
+            plan::plan_selector(model.plan.as_ref(), model.goal.is_available(&model.banner)),
+            strategy::strategy_selector(&model.strategy),
+            issues::issues_panel(&issues, &model.dismissed_issues),
+            div![
+                style![
+                    "display" => "flex";
+                    "align-items" => "center";
+                ],
+                if model.running || model.calculating {
+                    button![simple_ev(Ev::Click, Msg::CancelRun), "Stop"]
+                } else {
+                    button![
+                        simple_ev(Ev::Click, Msg::Run),
+                        if !issues.is_empty() {
+                            attrs![
+                                At::Disabled => true;
+                                At::Title => issues.join("\n");
+                            ]
+                        } else {
+                            attrs![]
+                        },
+                        if model.data.is_empty() || model.stale || model.exact_available() {
+                            "Run"
+                        } else {
+                            "More"
+                        }
+                    ]
+                },
+                if model.calculating {
+                    span![class!["padleft"], "Calculating..."]
+                } else {
+                    seed::empty()
+                },
+                if !model.data.is_empty() {
+                    button![
+                        class!["padleft"],
+                        simple_ev(Ev::Click, Msg::ResultsClear),
+                        "Reset results",
+                    ]
+                } else {
+                    seed::empty()
+                },
+                permalink(),
+                export::file_picker(
+                    "Import results",
+                    "Load results that were exported as JSON, along with their settings",
+                    |file| Msg::ResultsImport { file },
+                ),
+                label![
+                    class!["padleft"],
+                    attrs![
+                        At::For => "seed";
+                    ],
+                    "Seed:",
+                ],
+                input![
+                    id!["seed"],
+                    class!["padleft"],
+                    input_ev("input", |text| Msg::SeedChange {
+                        seed: text.parse::<u64>().ok(),
+                    }),
+                    attrs![
+                        At::Type => "number";
+                        At::Min => 0;
+                        At::Placeholder => "random";
+                        At::Value => model.seed.map(|x| x.to_string()).unwrap_or_default();
+                    ],
+                ],
+                input![
+                    id!["exact"],
+                    class!["padleft"],
+                    simple_ev(Ev::Input, Msg::ExactToggle),
+                    attrs![
+                        At::Type => "checkbox";
+                        At::Checked => model.exact.as_at_value();
+                        At::Disabled => (model.plan.is_some() || !exact::is_supported(&model.banner, &model.goal, &model.strategy)).as_at_value();
+                    ],
+                ],
+                label![
+                    attrs![
+                        At::For => "exact";
+                        At::Title => "Only available for goals of a single unit or any one of several units, on banners without sparks, focus charges, free summons, or guaranteed focus units";
+                    ],
+                    "Exact",
+                ],
+                input![
+                    id!["auto_run"],
+                    class!["padleft"],
+                    simple_ev(Ev::Input, Msg::AutoRunToggle),
+                    attrs![
+                        At::Type => "checkbox";
+                        At::Checked => model.auto_run.as_at_value();
+                    ],
+                ],
+                label![
+                    attrs![
+                        At::For => "auto_run";
+                    ],
+                    "Auto-run",
+                ],
+                precision_selector(model.precision, model.run_count),
+                if let Some(baseline) = &model.baseline {
+                    span![
+                        button![
+                            class!["padleft"],
+                            simple_ev(Ev::Click, Msg::BaselineClear),
+                            "Stop comparing",
+                        ],
+                        if baseline.is_usable(&model.goal) {
+                            seed::empty()
+                        } else {
+                            span![
+                                class!["padleft error"],
+                                "The goal can't be reached on the saved banner.",
+                            ]
+                        },
+                    ]
+                } else {
+                    let disabled = !model.is_runnable();
+                    let save_button = |with_goal, label| {
+                        button![
+                            class!["padleft"],
+                            simple_ev(Ev::Click, Msg::BaselineSave { with_goal }),
+                            if disabled {
+                                attrs![At::Disabled => true]
+                            } else {
+                                attrs![]
+                            },
+                            label,
+                        ]
+                    };
+                    span![
+                        save_button(true, "Compare to these settings"),
+                        save_button(false, "Compare to this banner"),
+                    ]
+                },
+            ],
+            results::results(
+                &model.data,
+                &model.side_stats,
+                &model.banner,
+                &model.strategy,
+                &goal,
+                match &model.plan {
+                    Some(plan) => Some(plan.budget),
+                    None => model.goal.budget(),
+                },
+                &model.comparison,
+                model.run_seed,
+                model.converged,
+                model.graph_highlight,
+                &model.percentiles,
+                model.lookup_chance,
+                model.lookup_orbs,
+                model.luck_orbs,
+                model.histogram_bin_width,
+                &model.pinned_curves,
+                model.cdf_hover,
+                model.log_scale,
+                model.measure,
+                &model.orb_prices,
+                model.summary_copied,
+                model.stale,
+                if model.running {
+                    Some(&model.progress)
+                } else {
+                    None
+                },
+            ),
+        ],
+    ]
+}
+
+/// Dropdown for choosing how long to simulate for, along with the number of
+/// runs when going for a set number of them.
+fn precision_selector(precision: Precision, run_count: Option<u32>) -> Vec<Node<Msg>> {
+    let mut select = select![
+        id!["precision"],
+        class!["padleft"],
+        input_ev("input", |text| {
+            let precision = text
+                .parse::<usize>()
+                .ok()
+                .and_then(|id| Precision::iter().find(|&precision| precision as usize == id));
+            if let Some(precision) = precision {
+                Msg::PrecisionChange { precision }
+            } else {
+                Msg::Null
+            }
+        }),
+    ];
+    for choice in Precision::iter() {
+        let mut attrs = attrs![
+            At::Value => choice as usize;
+        ];
+        if choice == precision {
+            attrs.add(At::Selected, "");
+        }
+        select.add_child(option![attrs, choice.to_string()]);
+    }
+    nodes![
+        label![
+            class!["padleft"],
+            attrs![
+                At::For => "precision";
+            ],
+            "Precision:",
+        ],
+        select,
+        if precision == Precision::Runs {
+            input![
+                id!["run_count"],
+                class!["padleft"],
+                input_ev("input", |text| Msg::RunCountChange {
+                    count: text.parse::<u32>().ok(),
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Min => 1;
+                    At::Placeholder => DEFAULT_RUN_COUNT;
+                    At::Value => run_count.map(|x| x.to_string()).unwrap_or_default();
+                ],
+            ]
+        } else {
+            seed::empty()
+        },
+    ]
+}
+
+fn permalink() -> Node<Msg> {
+    svg![
+        id!["permalink"],
+        class!["padleft"],
+        simple_ev(Ev::Click, Msg::Permalink),
+        attrs![
+            At::ViewBox => "0 0 150 50";
+        ],
+        rect![attrs![
+            At::Width => 60;
+            At::Height => 50;
+            "rx" => 25;
+            "x" => 5;
+        ]],
+        rect![attrs![
+            At::Width => 40;
+            At::Height => 30;
+            "rx" => 15;
+            At::Fill => "white";
+            "x" => 15;
+            "y" => 10;
+        ]],
+        rect![attrs![
+            At::Width => 60;
+            At::Height => 50;
+            "rx" => 25;
+            "x" => 75;
+        ]],
+        rect![attrs![
+            At::Width => 40;
+            At::Height => 30;
+            "rx" => 15;
+            At::Fill => "white";
+            "x" => 85;
+            "y" => 10;
+        ]],
+        rect![attrs![
+            At::Width => 65;
+            At::Height => 15;
+            At::Fill => "white";
+            "x" => 35;
+            "y" => 17.5;
+        ]],
+        rect![attrs![
+            At::Width => 60;
+            At::Height => 10;
+            "rx" => 5;
+            "x" => 40;
+            "y" => 20;
+        ]]
+    ]
+}
+
+/// Queue up messages based on the URL with which the application was loaded.
+fn routes(url: seed::Url) -> Option<Msg> {
+    let mut messages = vec![];
+
+    messages.push(match url.path.get(0).map(String::as_str) {
+        Some("help") => Msg::PageChange(Page::Help),
+        Some("changelog") => Msg::PageChange(Page::Changelog),
+        _ => Msg::PageChange(Page::Main),
+    });
+
+    let mut settings = Settings::default();
+    let mut invalid_query_string = false;
+
+    // Links from before the version was included are from the oldest format.
+    let version = match query_string::get(&url, "v") {
+        Some(version) => version.parse::<u32>().unwrap_or(0),
+        None => migrate::OLDEST_VERSION,
+    };
+    if version < migrate::OLDEST_VERSION || version > migrate::VERSION {
+        return Some(Msg::Alert {
+            message: "The permalink format has changed, please update your link.".into(),
+        });
+    }
+
+    if let Some(banner) = query_string::get(&url, "banner") {
+        if let Some(banner) = migrate::banner(banner, version) {
+            settings.banner = Some(banner);
+        } else {
+            invalid_query_string = true;
+        }
+    }
+
+    if let Some(goal) = query_string::get(&url, "goal") {
+        if let Some(goal) = migrate::goal(goal, version) {
+            settings.goal = Some(goal);
+        } else {
+            invalid_query_string = true;
+        }
+    }
+
+    if let Some(strategy) = query_string::get(&url, "strategy") {
+        if let Some(strategy) = migrate::strategy(strategy, version) {
+            settings.strategy = Some(strategy);
+        } else {
+            invalid_query_string = true;
+        }
+    }
+
+    if let Some(seed) = query_string::get(&url, "seed") {
+        if let Ok(seed) = seed.parse::<u64>() {
+            settings.seed = Some(seed);
+        } else {
+            invalid_query_string = true;
+        }
+    }
+
+    settings.run = query_string::get(&url, "run") == Some("1");
+
+    if invalid_query_string {
+        Some(Msg::Alert {
+            message: "Invalid permalink".into(),
+        })
+    } else {
+        if !settings.is_empty() {
+            messages.push(Msg::SettingsSet { settings });
+        }
+        Some(Msg::Multiple(messages))
+    }
+}
+
+#[wasm_bindgen]
+extern "C" {
+    fn alert(text: &str);
+}
+
+/// Sets up the model with anything remembered from earlier visits. Settings
+/// from a permalink replace the remembered ones afterwards.
+fn after_mount(_: seed::Url, orders: &mut impl Orders<Msg>) -> AfterMount<Model> {
+    let percentiles = storage::load::<Vec<f32>>(storage::PERCENTILES_KEY)
+        .filter(|percentiles| percentiles.iter().all(|&pct| pct > 0.0 && pct <= 1.0))
+        .unwrap_or_else(|| results::DEFAULT_PERCENTILES.to_vec());
+    let orb_prices = storage::load(storage::ORB_PRICES_KEY).unwrap_or_default();
+    let scenarios = storage::load(storage::SCENARIOS_KEY).unwrap_or_default();
+    let mut model = Model {
+        percentiles,
+        orb_prices,
+        scenarios,
+        ..Model::default()
+    };
+    if let Some(settings) = storage::load::<SavedSettings>(storage::SETTINGS_KEY) {
+        settings.restore(&mut model);
+    }
+    model.saved_settings = Some(SavedSettings::new(&model));
+    if model.auto_run {
+        orders.send_msg(Msg::Run);
+    }
+    AfterMount::new(model)
+}
+
+#[wasm_bindgen]
+pub fn render() {
+    seed::App::builder(update, view)
+        .after_mount(after_mount)
+        .routes(routes)
+        .build_and_start();
+}
@@ -1,76 +1,169 @@
 use seed::prelude::*;
 
-use crate::{Color, Msg};
+use std::convert::TryFrom;
 
-/// Representation of a summoning focus.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
-pub struct Banner {
-    pub focus_sizes: [i8; 4],
-    pub starting_rates: (u8, u8),
-    pub focus_charges: bool,
-    pub fourstar_focus: Option<Color>,
-}
+use strum::IntoEnumIterator;
 
-impl Default for Banner {
-    fn default() -> Self {
-        Banner {
-            focus_sizes: [1, 1, 1, 1],
-            starting_rates: (3, 3),
-            focus_charges: true,
-            fourstar_focus: None,
-        }
-    }
-}
+use fehsim_core::banner::{Banner, BannerType, PityReset, PoolSizes};
+use fehsim_core::units;
+use fehsim_core::Color;
 
-impl Banner {
-    /// Parses data from the representation used in query strings to share settings.
-    pub fn from_query_string(s: &str) -> Option<Self> {
-        let data = base64::decode(s).ok()?;
-        bincode::deserialize(&data).ok()
-    }
-}
+use crate::Msg;
+
+/// The usual starting rates, in tenths of a percent.
+const RATE_OPTIONS: [((u16, u16), &str); 5] = [
+    ((30, 30), "3%/3% (Normal)"),
+    ((50, 30), "5%/3% (Hero Fest)"),
+    ((80, 0), "8%/0% (Legendary)"),
+    ((40, 20), "4%/2% (Weekly Focus)"),
+    ((60, 0), "6%/0% (Double Special Heroes)"),
+];
 
 /// Section for choosing banner parameters.
 pub fn banner_selector(banner: &Banner) -> Node<Msg> {
-    let rate_option = |rates: (u8, u8), label: &str| -> Node<Msg> {
+    let mut rate_select = select![
+        id!["starting_rates"],
+        input_ev("input", |text| {
+            if let &[Ok(first), Ok(second)] = &*text
+                .split_whitespace()
+                .map(str::parse::<u16>)
+                .collect::<Vec<_>>()
+            {
+                Msg::BannerRateChange {
+                    rates: (first, second),
+                }
+            } else {
+                Msg::Null
+            }
+        }),
+    ];
+    if RATE_OPTIONS
+        .iter()
+        .all(|&(rates, _)| rates != banner.starting_rates)
+    {
+        rate_select.add_child(option![
+            attrs![
+                At::Value => "custom";
+                At::Selected => "";
+            ],
+            format!(
+                "{}%/{}% (Custom)",
+                banner.starting_rates.0 as f32 / 10.0,
+                banner.starting_rates.1 as f32 / 10.0
+            ),
+        ]);
+    }
+    for &(rates, label) in &RATE_OPTIONS {
         let mut attrs = attrs![
             At::Value => format!("{} {}", rates.0, rates.1);
         ];
         if rates == banner.starting_rates {
             attrs.add(At::Selected, "");
         }
-        option![attrs, label]
-    };
+        rate_select.add_child(option![attrs, label]);
+    }
+    let current_type = BannerType::of(banner);
+    let mut type_select = select![
+        id!["banner_type"],
+        input_ev("input", |text| {
+            if let Some(banner_type) = text
+                .parse::<u8>()
+                .ok()
+                .and_then(|id| BannerType::try_from(id).ok())
+            {
+                Msg::BannerTypeChange { banner_type }
+            } else {
+                Msg::Null
+            }
+        }),
+    ];
+    if current_type.is_none() {
+        type_select.add_child(option![
+            attrs![
+                At::Value => "custom";
+                At::Selected => "";
+            ],
+            "Custom banner",
+        ]);
+    }
+    for banner_type in BannerType::iter() {
+        let mut attrs = attrs![
+            At::Value => banner_type as usize;
+        ];
+        if current_type == Some(banner_type) {
+            attrs.add(At::Selected, "");
+        }
+        type_select.add_child(option![attrs, banner_type.to_string()]);
+    }
     div![
         id!["banner_selector"],
         div![
-            select![
-                id!["starting_rates"],
-                input_ev("input", |text| {
-                    if let &[Ok(first), Ok(second)] = &*text
-                        .split_whitespace()
-                        .map(str::parse::<u8>)
-                        .collect::<Vec<_>>()
-                    {
-                        Msg::BannerRateChange {
-                            rates: (first, second),
-                        }
-                    } else {
-                        Msg::Null
-                    }
-                }),
-                rate_option((3, 3), "3%/3% (Normal)"),
-                rate_option((5, 3), "5%/3% (Hero Fest)"),
-                rate_option((8, 0), "8%/0% (Legendary)"),
-                rate_option((4, 2), "4%/2% (Weekly Focus)"),
-                rate_option((6, 0), "6%/0% (Double Special Heroes)"),
+            label![
+                attrs![
+                    At::For => "banner_name";
+                ],
+                "Banner name:",
+            ],
+            input![
+                id!["banner_name"],
+                class!["padleft"],
+                input_ev("input", |name| Msg::BannerNameChange { name }),
+                attrs![
+                    At::Placeholder => "optional, like \"Legendary Eitri, Jan 2025\"";
+                    At::Value => banner.name.as_deref().unwrap_or_default();
+                ],
+            ],
+        ],
+        div![type_select],
+        div![
+            rate_select,
+            // Focus charges are earned from off-focus 5* units, so they can't
+            // do anything on banners like legendary banners that don't have any.
+            if banner.starting_rates.1 > 0 {
+                nodes![
+                    input![
+                        id!["focus_charges_banner"],
+                        simple_ev(Ev::Input, Msg::BannerFocusChargesToggle),
+                        attrs![
+                            At::Type => "checkbox";
+                            At::Checked => banner.focus_charges.as_at_value();
+                        ],
+                    ],
+                    label![attrs![At::For => "focus_charges_banner"], "Focus charges?"],
+                ]
+            } else {
+                vec![]
+            },
+            input![
+                id!["spark_banner"],
+                simple_ev(Ev::Input, Msg::BannerSparkToggle),
+                attrs![At::Type => "checkbox"; At::Checked => banner.spark.as_at_value()],
+            ],
+            label![attrs![At::For => "spark_banner"], "Spark?"],
+            input![
+                id!["fourstar_special_banner"],
+                simple_ev(Ev::Input, Msg::BannerFourstarSpecialToggle),
+                attrs![
+                    At::Type => "checkbox";
+                    At::Checked => banner.fourstar_special.as_at_value();
+                ],
+            ],
+            label![
+                attrs![At::For => "fourstar_special_banner"],
+                "4* special rate?"
             ],
             input![
-                id!["focus_charges_banner"],
-                simple_ev(Ev::Input, Msg::BannerFocusChargesToggle),
-                attrs![At::Type => "checkbox"; At::Checked => banner.focus_charges.as_at_value()],
+                id!["free_first_summon_banner"],
+                simple_ev(Ev::Input, Msg::BannerFreeFirstSummonToggle),
+                attrs![
+                    At::Type => "checkbox";
+                    At::Checked => banner.free_first_summon.as_at_value();
+                ],
             ],
-            label![attrs![At::For => "focus_charges_banner"], "Focus charges?"]
+            label![
+                attrs![At::For => "free_first_summon_banner"],
+                "Free first summon?"
+            ]
         ],
         div![
             id!["focus_counts"],
@@ -206,67 +299,45 @@ pub fn banner_selector(banner: &Banner) -> Node<Msg> {
                     At::Required => true;
                 ],
             ],
-            if banner.starting_rates == (3, 3) {
+        ],
+        div![
+            id!["focus_names"],
+            "Names:",
+            focus_names_input(banner, Color::Red, "R:"),
+            focus_names_input(banner, Color::Blue, "B:"),
+            focus_names_input(banner, Color::Green, "G:"),
+            focus_names_input(banner, Color::Colorless, "C:"),
+        ],
+        div![
+            id!["fourstar_focus_counts"],
+            "4* focus:",
+            fourstar_focus_input(banner, Color::Red, "R:"),
+            fourstar_focus_input(banner, Color::Blue, "B:"),
+            fourstar_focus_input(banner, Color::Green, "G:"),
+            fourstar_focus_input(banner, Color::Colorless, "C:"),
+            if banner.has_fourstar_focus() {
                 nodes![
                     label![
                         attrs![
-                            At::For => "fourstar_focus";
+                            At::For => "fourstar_focus_rate";
                         ],
-                        "4* focus:",
+                        "Rate (%):",
                     ],
-                    select![
-                        id!["fourstar_focus"],
+                    input![
+                        id!["fourstar_focus_rate"],
                         input_ev("input", |text| {
-                            use Color::*;
-                            let focus = match &*text {
-                                "Red" => Some(Red),
-                                "Blue" => Some(Blue),
-                                "Green" => Some(Green),
-                                "Colorless" => Some(Colorless),
-                                _ => None,
-                            };
-
-                            Msg::BannerFourstarFocusChange { focus }
-                        }),
-                        option![
-                            if banner.fourstar_focus == None {
-                                attrs![At::Selected => "1"]
-                            } else {
-                                attrs![]
-                            },
-                            "None"
-                        ],
-                        option![
-                            if banner.fourstar_focus == Some(Color::Red) {
-                                attrs![At::Selected => "1"]
-                            } else {
-                                attrs![]
-                            },
-                            "Red"
-                        ],
-                        option![
-                            if banner.fourstar_focus == Some(Color::Blue) {
-                                attrs![At::Selected => "1"]
-                            } else {
-                                attrs![]
-                            },
-                            "Blue"
-                        ],
-                        option![
-                            if banner.fourstar_focus == Some(Color::Green) {
-                                attrs![At::Selected => "1"]
-                            } else {
-                                attrs![]
-                            },
-                            "Green"
-                        ],
-                        option![
-                            if banner.fourstar_focus == Some(Color::Colorless) {
-                                attrs![At::Selected => "1"]
+                            if let Ok(rate) = text.parse::<u8>() {
+                                Msg::BannerFourstarFocusRateChange { rate }
                             } else {
-                                attrs![]
-                            },
-                            "Colorless"
+                                Msg::Null
+                            }
+                        }),
+                        attrs![
+                            At::Type => "number";
+                            At::Class => "small_number";
+                            At::Value => banner.fourstar_focus_rate;
+                            At::Min => 0;
+                            At::Required => true;
                         ],
                     ],
                 ]
@@ -274,5 +345,496 @@ pub fn banner_selector(banner: &Banner) -> Node<Msg> {
                 vec![]
             }
         ],
+        advanced_banner_selector(banner),
+    ]
+}
+
+/// Label and input for the names of the focus units of the given color,
+/// separated by commas, along with a list of heroes to add them from.
+fn focus_names_input(banner: &Banner, color: Color, label: &str) -> Vec<Node<Msg>> {
+    let id = format!("focus_names_{}", color as usize);
+    let names = banner.focus_names[color as usize].clone();
+    let mut picker = select![
+        class!["padleft"],
+        input_ev("input", move |name| {
+            if name.is_empty() {
+                return Msg::Null;
+            }
+            let mut names = names.clone();
+            names.push(name);
+            Msg::BannerFocusNamesChange { color, names }
+        }),
+        option![attrs![At::Value => ""], "Add a hero"],
+    ];
+    for unit in units::of_color(color) {
+        let listed = banner.focus_names[color as usize]
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(unit.name));
+        if !listed {
+            picker.add_child(option![attrs![At::Value => unit.name], unit.name]);
+        }
+    }
+    nodes![
+        label![
+            attrs![
+                At::For => &id;
+            ],
+            label,
+        ],
+        input![
+            input_ev("input", move |text| {
+                let names = text
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect();
+                Msg::BannerFocusNamesChange { color, names }
+            }),
+            attrs![
+                At::Id => &id;
+                At::Placeholder => "optional, separated by commas";
+                At::Value => banner.focus_names[color as usize].join(", ");
+            ]
+        ],
+        picker,
+    ]
+}
+
+/// Label and input for the number of 4* focus units of the given color.
+fn fourstar_focus_input(banner: &Banner, color: Color, label: &str) -> Vec<Node<Msg>> {
+    let id = format!("fourstar_focus_count_{}", color as usize);
+    let count = banner.fourstar_focus_sizes[color as usize];
+    nodes![
+        label![
+            attrs![
+                At::For => &id;
+            ],
+            label,
+        ],
+        input![
+            input_ev("input", move |text| {
+                Msg::BannerFourstarFocusSizeChange {
+                    color,
+                    quantity: text.parse::<i8>().unwrap_or(-1),
+                }
+            }),
+            attrs![
+                At::Id => &id;
+                At::Type => "number";
+                At::Class => "small_number";
+                At::Value => if count >= 0 {
+                    count.to_string()
+                } else {
+                    "".to_string()
+                };
+                At::Min => 0;
+                At::Required => true;
+            ]
+        ],
+    ]
+}
+
+/// Subsection for banner parameters that only need to be changed for unusual
+/// banners or hypothetical ones.
+fn advanced_banner_selector(banner: &Banner) -> Node<Msg> {
+    let mut pity_reset_select = select![
+        id!["pity_reset"],
+        input_ev("input", |text| {
+            let reset = text
+                .parse::<usize>()
+                .ok()
+                .and_then(|id| PityReset::iter().find(|&reset| reset as usize == id));
+            if let Some(reset) = reset {
+                Msg::BannerPityResetChange { reset }
+            } else {
+                Msg::Null
+            }
+        }),
+    ];
+    for reset in PityReset::iter() {
+        let mut attrs = attrs![
+            At::Value => reset as usize;
+        ];
+        if reset == banner.pity_reset {
+            attrs.add(At::Selected, "");
+        }
+        pity_reset_select.add_child(option![attrs, reset.to_string()]);
+    }
+    let (focus_rate, fivestar_rate) = banner.starting_rates;
+    let (fourstar_rate, threestar_rate) = banner.lower_rates;
+    details![
+        id!["advanced_banner"],
+        summary!["Advanced"],
+        div![
+            label![
+                attrs![
+                    At::For => "focus_rate";
+                ],
+                "Starting rates (%): focus",
+            ],
+            input![
+                id!["focus_rate"],
+                input_ev("input", move |text| {
+                    if let Ok(rate) = text.parse::<f32>() {
+                        let focus_rate = (rate * 10.0).round().max(1.0).min(1000.0) as u16;
+                        Msg::BannerRateChange {
+                            rates: (focus_rate, fivestar_rate.min(1000 - focus_rate)),
+                        }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => focus_rate as f32 / 10.0;
+                    At::Min => 0.1;
+                    At::Max => 100;
+                    At::Step => 0.1;
+                    At::Required => true;
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "fivestar_rate";
+                ],
+                "off-focus",
+            ],
+            input![
+                id!["fivestar_rate"],
+                input_ev("input", move |text| {
+                    if let Ok(rate) = text.parse::<f32>() {
+                        let limit = (1000 - focus_rate) as f32;
+                        Msg::BannerRateChange {
+                            rates: (focus_rate, (rate * 10.0).round().max(0.0).min(limit) as u16),
+                        }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => fivestar_rate as f32 / 10.0;
+                    At::Min => 0;
+                    At::Max => 100;
+                    At::Step => 0.1;
+                    At::Required => true;
+                ],
+            ],
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "fourstar_rate";
+                ],
+                "4* rate (%):",
+            ],
+            input![
+                id!["fourstar_rate"],
+                input_ev("input", move |text| {
+                    if let Ok(rate) = text.parse::<f32>() {
+                        Msg::BannerLowerRatesChange {
+                            rates: (
+                                (rate * 10.0).round().max(0.0).min(1000.0) as u16,
+                                threestar_rate,
+                            ),
+                        }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => fourstar_rate as f32 / 10.0;
+                    At::Min => 0;
+                    At::Max => 100;
+                    At::Step => 0.1;
+                    At::Required => true;
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "threestar_rate";
+                ],
+                "3* rate (%):",
+            ],
+            input![
+                id!["threestar_rate"],
+                input_ev("input", move |text| {
+                    if let Ok(rate) = text.parse::<f32>() {
+                        Msg::BannerLowerRatesChange {
+                            rates: (
+                                fourstar_rate,
+                                (rate * 10.0).round().max(0.0).min(1000.0) as u16,
+                            ),
+                        }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => threestar_rate as f32 / 10.0;
+                    At::Min => 0;
+                    At::Max => 100;
+                    At::Step => 0.1;
+                    At::Required => true;
+                ],
+            ],
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "starting_pity";
+                ],
+                "Summons already made without a 5*:",
+            ],
+            input![
+                id!["starting_pity"],
+                input_ev("input", |text| {
+                    Msg::BannerStartingPityChange {
+                        summons: text.parse::<u16>().unwrap_or(0),
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.starting_pity;
+                    At::Min => 0;
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "starting_spark";
+                ],
+                "towards spark:",
+            ],
+            input![
+                id!["starting_spark"],
+                input_ev("input", |text| {
+                    Msg::BannerStartingSparkChange {
+                        summons: text.parse::<u16>().unwrap_or(0),
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.starting_spark;
+                    At::Min => 0;
+                    At::Max => 39;
+                ],
+            ],
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "tickets";
+                ],
+                "Summoning tickets:",
+            ],
+            input![
+                id!["tickets"],
+                input_ev("input", |text| {
+                    Msg::BannerTicketsChange {
+                        tickets: text.parse::<u16>().unwrap_or(0),
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.tickets;
+                    At::Min => 0;
+                ],
+            ],
+        ],
+        div![
+            input![
+                id!["daily_free_summon"],
+                simple_ev(Ev::Input, Msg::BannerDailyFreeSummonToggle),
+                attrs![
+                    At::Type => "checkbox";
+                    At::Checked => banner.daily_free_summon.as_at_value();
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "daily_free_summon";
+                ],
+                "Daily free summon for",
+            ],
+            input![
+                id!["duration_days"],
+                input_ev("input", |text| {
+                    if let Ok(days) = text.parse::<u16>() {
+                        Msg::BannerDurationChange { days }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.duration_days;
+                    At::Min => 0;
+                    At::Required => true;
+                ],
+            ],
+            " days",
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "pity_step";
+                ],
+                "Rate increase (%):",
+            ],
+            input![
+                id!["pity_step"],
+                input_ev("input", |text| {
+                    if let Ok(step) = text.parse::<f32>() {
+                        Msg::BannerPityStepChange {
+                            step: (step * 10.0).round().max(0.0).min(255.0) as u8,
+                        }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.pity_step as f32 / 10.0;
+                    At::Min => 0;
+                    At::Step => 0.1;
+                    At::Required => true;
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "pity_interval";
+                ],
+                "every",
+            ],
+            input![
+                id!["pity_interval"],
+                input_ev("input", |text| {
+                    if let Ok(interval) = text.parse::<u8>() {
+                        Msg::BannerPityIntervalChange { interval }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.pity_interval;
+                    At::Min => 1;
+                    At::Required => true;
+                ],
+            ],
+            " summons",
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "hard_pity";
+                    At::Title => "The guaranteed unit comes from the next stone opened of a color with focus units";
+                ],
+                "Guaranteed focus unit after",
+            ],
+            input![
+                id!["hard_pity"],
+                input_ev("input", |text| {
+                    let threshold = text.parse::<u16>().ok().filter(|&x| x > 0);
+                    Msg::BannerHardPityChange { threshold }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => banner.hard_pity.map(|x| x.to_string()).unwrap_or_default();
+                    At::Min => 1;
+                    At::Placeholder => "never";
+                ],
+            ],
+            if banner.hard_pity.is_some() && banner.focus_sizes.iter().any(|&size| size <= 0) {
+                " summons without one, on the next stone of a focus color"
+            } else {
+                " summons without one"
+            },
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "pity_reset";
+                ],
+                "Rate reset:",
+            ],
+            pity_reset_select,
+        ],
+        pool_sizes_selector(banner.pool_sizes),
+    ]
+}
+
+/// Table of the number of units of each color in the pools outside of the
+/// focus.
+fn pool_sizes_selector(sizes: PoolSizes) -> Node<Msg> {
+    let rows: [(&str, fn(&mut PoolSizes) -> &mut [u8; 4]); 5] = [
+        ("5*", |sizes| &mut sizes.fivestar),
+        ("5*-exclusive", |sizes| &mut sizes.fivestar_exclusive),
+        ("4* special rate", |sizes| &mut sizes.fourstar_special),
+        ("4*", |sizes| &mut sizes.fourstar),
+        ("3*", |sizes| &mut sizes.threestar),
+    ];
+    let mut table = table![tr![
+        th!["Units in each pool"],
+        Color::iter()
+            .map(|color| th![color.to_string()])
+            .collect::<Vec<_>>(),
+    ]];
+    for &(label, field) in &rows {
+        let mut row = tr![th![label]];
+        for color in Color::iter() {
+            let mut copy = sizes;
+            let count = field(&mut copy)[color as usize];
+            row.add_child(td![input![
+                input_ev("input", move |text| {
+                    if let Ok(count) = text.parse::<u8>() {
+                        let mut sizes = sizes;
+                        field(&mut sizes)[color as usize] = count;
+                        Msg::BannerPoolSizesChange { sizes }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Value => count;
+                    At::Min => 0;
+                    At::Required => true;
+                ],
+            ]]);
+        }
+        table.add_child(row);
+    }
+    div![
+        id!["pool_sizes"],
+        table,
+        if sizes != PoolSizes::default() {
+            button![
+                simple_ev(
+                    Ev::Click,
+                    Msg::BannerPoolSizesChange {
+                        sizes: PoolSizes::default()
+                    }
+                ),
+                "Reset to the current game's pools",
+            ]
+        } else {
+            seed::empty()
+        },
     ]
 }
@@ -0,0 +1,103 @@
+//! Picking a banner from the feed of banners running in the game, which is
+//! served next to the page. See `fehsim_core::catalog` for its format.
+
+use seed::fetch;
+use seed::prelude::*;
+
+use fehsim_core::catalog::CatalogEntry;
+use fehsim_core::date::Date;
+
+use crate::Msg;
+
+/// Where the feed of current banners is. It's `banners.json` from the root
+/// of the repository, uploaded next to the page when deploying.
+const CATALOG_URL: &str = "/banners.json";
+
+/// The banners from the feed, once they've been asked for.
+#[derive(Debug)]
+pub enum Catalog {
+    NotLoaded,
+    Loading,
+    Loaded(Vec<CatalogEntry>),
+    /// The feed couldn't be loaded, for the given reason.
+    Failed(String),
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Catalog::NotLoaded
+    }
+}
+
+/// Today's date where the page is being viewed.
+pub fn today() -> Date {
+    let now = js_sys::Date::new_0();
+    Date::from_ymd(
+        now.get_full_year() as i64,
+        now.get_month() + 1,
+        now.get_date(),
+    )
+    .unwrap()
+}
+
+/// Loads the feed of current banners.
+pub async fn fetch_catalog() -> Result<Msg, Msg> {
+    fetch::Request::new(CATALOG_URL)
+        .fetch_json_data(|result: fetch::ResponseDataResult<Vec<CatalogEntry>>| {
+            Msg::CatalogFetched {
+                entries: result.map_err(|err| format!("{:?}", err)),
+            }
+        })
+        .await
+}
+
+/// Section for loading the current banners and picking one of them.
+pub fn catalog_selector(catalog: &Catalog) -> Node<Msg> {
+    match catalog {
+        Catalog::NotLoaded => div![
+            id!["catalog"],
+            button![
+                simple_ev(Ev::Click, Msg::CatalogLoad),
+                "Load current banners"
+            ],
+        ],
+        Catalog::Loading => div![id!["catalog"], "Loading current banners..."],
+        Catalog::Failed(reason) => div![
+            id!["catalog"],
+            button![simple_ev(Ev::Click, Msg::CatalogLoad), "Try again"],
+            span![
+                class!["padleft", "error"],
+                format!("Couldn't load the current banners: {}", reason),
+            ],
+        ],
+        Catalog::Loaded(entries) if entries.is_empty() => {
+            div![id!["catalog"], "No current banners are listed."]
+        }
+        Catalog::Loaded(entries) => {
+            let mut select = select![
+                id!["catalog_banner"],
+                input_ev("input", |text| match text.parse::<usize>() {
+                    Ok(index) => Msg::CatalogPick { index },
+                    Err(_) => Msg::Null,
+                }),
+                option![attrs![At::Value => ""], "Pick a current banner"],
+            ];
+            let today = today();
+            for (index, entry) in entries.iter().enumerate() {
+                let label = match (entry.start(), entry.days_left(today)) {
+                    (Some(start), _) if start > today => format!(
+                        "{} (starts in {} days)",
+                        entry.name,
+                        today.days_until(start)
+                    ),
+                    (_, Some(0)) => format!("{} (ended)", entry.name),
+                    (_, Some(1)) => format!("{} (last day)", entry.name),
+                    (_, Some(days)) => format!("{} ({} days left)", entry.name, days),
+                    (_, None) => entry.name.clone(),
+                };
+                select.add_child(option![attrs![At::Value => index], label]);
+            }
+            div![id!["catalog"], select]
+        }
+    }
+}
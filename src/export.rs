@@ -0,0 +1,370 @@
+//! Saving the results and settings to files, for looking at them in other
+//! programs or bringing them back later.
+
+use std::fmt::Write;
+
+use seed::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use fehsim_core::banner::Banner;
+use fehsim_core::counter::Counter;
+use fehsim_core::goal::Goal;
+use fehsim_core::migrate;
+use fehsim_core::stats;
+use fehsim_core::strategy::Strategy;
+
+use crate::results::{self, charts, SideStats};
+use crate::{plan, Msg};
+
+/// Everything needed to look at a set of results again later: the settings
+/// that they were simulated with, and what happened in the runs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResultsFile {
+    /// The version of the format that the settings are written in, the same
+    /// as the one used for permalinks.
+    pub version: u32,
+    pub banner: Banner,
+    pub goal: Goal,
+    pub strategy: Strategy,
+    /// The seed that the results were simulated with, if one was chosen.
+    pub seed: Option<u64>,
+    /// The number of runs simulated.
+    pub runs: u64,
+    /// The number of runs that took each number of orbs.
+    pub data: Counter,
+    /// Missing from exact results, which don't come with any.
+    #[serde(default)]
+    pub side_stats: SideStats,
+}
+
+impl ResultsFile {
+    /// The results as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Reads results that were exported before, explaining what's wrong with
+    /// them if they can't be read. Settings from older versions are brought up
+    /// to date the same way as in permalinks.
+    pub fn from_json(text: &str) -> Result<ResultsFile, String> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        /// The file with its settings left as they were written, until it's
+        /// known which version to read them as.
+        #[derive(Deserialize)]
+        struct Saved {
+            banner: serde_json::Value,
+            goal: serde_json::Value,
+            strategy: serde_json::Value,
+            seed: Option<u64>,
+            runs: u64,
+            data: Counter,
+            #[serde(default)]
+            side_stats: SideStats,
+        }
+        let version = serde_json::from_str::<Version>(text)
+            .map_err(|_| "That isn't a file of exported results.".to_string())?
+            .version;
+        if version < migrate::OLDEST_VERSION || version > migrate::VERSION {
+            return Err(
+                "Those results were exported from a different version of the simulator, and can't be read anymore."
+                    .to_string(),
+            );
+        }
+        let saved: Saved = serde_json::from_str(text)
+            .map_err(|err| format!("The results couldn't be read: {}", err))?;
+        let settings = (
+            migrate::banner(Json(saved.banner), version),
+            migrate::goal(Json(saved.goal), version),
+            migrate::strategy(Json(saved.strategy), version),
+        );
+        match settings {
+            (Some(banner), Some(goal), Some(strategy)) => Ok(ResultsFile {
+                version: migrate::VERSION,
+                banner,
+                goal,
+                strategy,
+                seed: saved.seed,
+                runs: saved.runs,
+                data: saved.data,
+                side_stats: saved.side_stats,
+            }),
+            _ => Err("The settings in the file couldn't be read.".to_string()),
+        }
+    }
+}
+
+/// Settings written as JSON, for reading with the version that they were
+/// written in.
+struct Json(serde_json::Value);
+
+impl migrate::Source for Json {
+    fn read<T: DeserializeOwned>(self) -> Option<T> {
+        serde_json::from_value(self.0).ok()
+    }
+}
+
+/// Reads a file that the user picked, and sends its contents with the message
+/// from `on_read`.
+pub async fn read_file(
+    file: web_sys::File,
+    on_read: fn(Result<String, String>) -> Msg,
+) -> Result<Msg, Msg> {
+    let text = JsFuture::from(file.text())
+        .await
+        .ok()
+        .and_then(|text| text.as_string())
+        .ok_or_else(|| "The file couldn't be opened.".to_string());
+    Ok(on_read(text))
+}
+
+/// Button for picking a JSON file to import, which sends the message from
+/// `on_pick` with the file that was picked.
+pub fn file_picker(label: &str, title: &str, on_pick: fn(web_sys::File) -> Msg) -> Node<Msg> {
+    label![
+        class!["padleft"],
+        attrs![
+            At::Title => title;
+        ],
+        label,
+        input![
+            class!["padleft"],
+            ev(Ev::Change, move |event| {
+                let file = event
+                    .target()
+                    .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+                    .and_then(|input| input.files())
+                    .and_then(|files| files.get(0));
+                match file {
+                    Some(file) => on_pick(file),
+                    None => Msg::Null,
+                }
+            }),
+            attrs![
+                At::Type => "file";
+                At::Accept => ".json,application/json";
+            ],
+        ],
+    ]
+}
+
+/// Has the browser download a file with the given contents.
+pub fn download(filename: &str, mime_type: &str, contents: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(mime_type);
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let link = seed::document()
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()?;
+    link.set_href(&url);
+    link.set_download(filename);
+    link.click();
+    web_sys::Url::revoke_object_url(&url)
+}
+
+/// The rules from `style.css` that the charts need to look the same outside
+/// of the page, with a background so that they show up on dark themes too.
+const CHART_STYLE: &str = "
+svg { background-color: white; font-family: sans-serif; }
+#graph_highlights > circle, .chart_tooltip > circle { fill: rgb(206, 40, 40); }
+#histogram_bars > rect { fill: rgb(80, 80, 80); }
+.chart_line { fill: none; stroke: black; stroke-width: 0.5px; }
+.chart_marker { stroke: rgb(206, 40, 40); stroke-width: 0.3px; stroke-dasharray: 1 1; }
+";
+
+/// The chart with the given id as a standalone SVG file, if it's on the page.
+pub fn chart_svg(id: &str) -> Option<String> {
+    let markup = seed::document().get_element_by_id(id)?.outer_html();
+    // The chart's opening tag is followed by the size and styling that it
+    // would otherwise get from the page.
+    let tag_end = markup.find('>')?;
+    let (tag, rest) = markup.split_at(tag_end);
+    let mut svg = tag.to_string();
+    if !svg.contains("xmlns=") {
+        svg.push_str(" xmlns=\"http://www.w3.org/2000/svg\"");
+    }
+    write!(
+        svg,
+        " width=\"{}\" height=\"{}\">",
+        charts::WIDTH * 10.0,
+        charts::HEIGHT * 10.0
+    )
+    .unwrap();
+    write!(svg, "<style>{}</style>", CHART_STYLE).unwrap();
+    svg.push_str(&rest[1..]);
+    Some(svg)
+}
+
+/// A field of a CSV file, quoted if it needs to be.
+fn csv_field(text: &str) -> String {
+    if text.contains(|c| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// A rate in tenths of a percent, for display.
+fn rate_label(tenths: u16) -> String {
+    format!("{}%", tenths as f32 / 10.0)
+}
+
+/// Puts the text on the clipboard. Goes through JavaScript directly, since the
+/// clipboard isn't part of the stable web APIs yet.
+pub async fn copy_to_clipboard(text: &str) -> Result<(), JsValue> {
+    let navigator = js_sys::Reflect::get(&seed::window(), &"navigator".into())?;
+    let clipboard = js_sys::Reflect::get(&navigator, &"clipboard".into())?;
+    let write_text =
+        js_sys::Reflect::get(&clipboard, &"writeText".into())?.dyn_into::<js_sys::Function>()?;
+    let promise = write_text
+        .call1(&clipboard, &JsValue::from_str(text))?
+        .dyn_into::<js_sys::Promise>()?;
+    JsFuture::from(promise).await.map(|_| ())
+}
+
+/// A short description of the banner, with its name if it has one.
+fn banner_label(banner: &Banner) -> String {
+    format!(
+        "{} ({}/{})",
+        banner.name.as_deref().unwrap_or("Custom banner"),
+        rate_label(banner.starting_rates.0),
+        rate_label(banner.starting_rates.1)
+    )
+}
+
+/// A summary of the results as Markdown: a line describing the banner and
+/// goal, a line of overall numbers, and a table of the chosen percentiles.
+/// The table's columns are lined up so that it still reads well where tables
+/// aren't supported, like on Discord.
+pub fn summary_markdown(
+    data: &Counter,
+    unit: &str,
+    banner: &Banner,
+    goal: &Goal,
+    percentiles: &[f32],
+) -> String {
+    let runs: u64 = data.iter().map(|&count| count as u64).sum();
+    let mut summary = String::new();
+    writeln!(
+        summary,
+        "**{}**, goal: **{}**",
+        banner_label(banner),
+        plan::goal_label(goal)
+    )
+    .unwrap();
+    writeln!(summary).unwrap();
+    writeln!(
+        summary,
+        "Average: {:.1} {}, median: {}, from {} runs",
+        stats::mean(data),
+        unit,
+        stats::percentile(data, 0.5),
+        runs
+    )
+    .unwrap();
+    if !percentiles.is_empty() {
+        let rows: Vec<(String, String)> = percentiles
+            .iter()
+            .zip(stats::percentiles(data, percentiles))
+            .map(|(&pct, value)| {
+                (
+                    format!("{}%", results::percent_label(pct)),
+                    value.to_string(),
+                )
+            })
+            .collect();
+        let header = ("Chance", unit);
+        let widths = rows
+            .iter()
+            .fold((header.0.len(), header.1.len()), |(chance, value), row| {
+                (chance.max(row.0.len()), value.max(row.1.len()))
+            });
+        writeln!(summary).unwrap();
+        writeln!(
+            summary,
+            "| {:<w0$} | {:>w1$} |",
+            header.0,
+            header.1,
+            w0 = widths.0,
+            w1 = widths.1
+        )
+        .unwrap();
+        writeln!(
+            summary,
+            "|{}|{}:|",
+            "-".repeat(widths.0 + 2),
+            "-".repeat(widths.1 + 1)
+        )
+        .unwrap();
+        for (chance, value) in rows {
+            writeln!(
+                summary,
+                "| {:<w0$} | {:>w1$} |",
+                chance,
+                value,
+                w0 = widths.0,
+                w1 = widths.1
+            )
+            .unwrap();
+        }
+    }
+    summary
+}
+
+/// The results as a CSV file: a few lines describing the banner and goal,
+/// then the number of runs that took each number of orbs along with the
+/// chance of having reached the goal by then.
+pub fn results_csv(data: &Counter, banner: &Banner, goal: &Goal, seed: Option<u64>) -> String {
+    let total: u64 = data.iter().map(|&count| count as u64).sum();
+    let mut csv = String::new();
+    let focus_sizes = banner
+        .focus_sizes
+        .iter()
+        .map(|size| size.max(&0).to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut metadata = vec![
+        ("Banner", banner.name.clone().unwrap_or_default()),
+        (
+            "Starting rates",
+            format!(
+                "{}/{}",
+                rate_label(banner.starting_rates.0),
+                rate_label(banner.starting_rates.1)
+            ),
+        ),
+        ("Focus units (R/B/G/C)", focus_sizes),
+        ("Goal", plan::goal_label(goal)),
+        ("Runs", total.to_string()),
+    ];
+    if let Some(seed) = seed {
+        metadata.push(("Seed", seed.to_string()));
+    }
+    for (key, value) in metadata {
+        writeln!(csv, "{},{}", csv_field(key), csv_field(&value)).unwrap();
+    }
+    writeln!(csv).unwrap();
+    writeln!(csv, "Orbs,Runs,Cumulative chance").unwrap();
+    let mut so_far = 0;
+    for (orbs, &count) in data.iter().enumerate() {
+        so_far += count as u64;
+        if count > 0 {
+            writeln!(
+                csv,
+                "{},{},{:.6}",
+                orbs,
+                count,
+                so_far as f64 / total as f64
+            )
+            .unwrap();
+        }
+    }
+    csv
+}
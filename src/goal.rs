@@ -1,245 +1,20 @@
 use seed::prelude::*;
 
 use std::convert::TryFrom;
-use std::fmt;
 
 use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
 
-use serde::{Deserialize, Serialize};
+use fehsim_core::banner::Banner;
+use fehsim_core::goal::{Goal, GoalKind, GoalPreset};
+use fehsim_core::{Color, Pool};
 
-use crate::{banner::Banner, Color, Msg};
+use crate::Msg;
 
-/// Pre-set options for common goals.
-#[derive(Copy, Clone, Debug, EnumIter, PartialEq, Eq, Serialize, Deserialize)]
-pub enum GoalPreset {
-    AnyFocus,
-    AllFocus,
-    RedFocus,
-    AnyRed,
-    RedFourstarFocus,
-    BlueFocus,
-    AnyBlue,
-    BlueFourstarFocus,
-    GreenFocus,
-    AnyGreen,
-    GreenFourstarFocus,
-    ColorlessFocus,
-    AnyColorless,
-    ColorlessFourstarFocus,
-}
-
-impl fmt::Display for GoalPreset {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use crate::goal::GoalPreset::*;
-        let s = match *self {
-            AnyFocus => "Any 5* focus unit",
-            AllFocus => "All focus units",
-            RedFocus => "Specific red 5* focus unit",
-            RedFourstarFocus => "The red 4* focus unit",
-            AnyRed => "Any red 5* focus unit",
-            BlueFocus => "Specific blue 5* focus unit",
-            BlueFourstarFocus => "The blue 4* focus unit",
-            AnyBlue => "Any blue 5* focus unit",
-            GreenFocus => "Specific green 5* focus unit",
-            GreenFourstarFocus => "The green 4* focus unit",
-            AnyGreen => "Any green 5* focus unit",
-            ColorlessFocus => "Specific colorless 5* focus unit",
-            AnyColorless => "Any colorless 5* focus unit",
-            ColorlessFourstarFocus => "The colorless 4* focus unit",
-        };
-        f.write_str(s)
-    }
-}
-
-impl TryFrom<u8> for GoalPreset {
-    type Error = ();
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        for variant in GoalPreset::iter() {
-            if variant as usize == value as usize {
-                return Ok(variant);
-            }
-        }
-        Err(())
-    }
-}
-
-impl GoalPreset {
-    /// Determines whether or not the selected preset is a goal that it is
-    /// possible to achieve on the banner.
-    pub fn is_available(self, banner: &Banner) -> bool {
-        use GoalPreset::*;
-        match self {
-            AnyFocus | AllFocus => banner.focus_sizes.iter().any(|&x| x > 0),
-            RedFocus | AnyRed => banner.focus_sizes[0] > 0,
-            BlueFocus | AnyBlue => banner.focus_sizes[1] > 0,
-            GreenFocus | AnyGreen => banner.focus_sizes[2] > 0,
-            ColorlessFocus | AnyColorless => banner.focus_sizes[3] > 0,
-            RedFourstarFocus => {
-                banner.fourstar_focus == Some(Color::Red) && banner.focus_sizes[0] > 0
-            }
-            BlueFourstarFocus => {
-                banner.fourstar_focus == Some(Color::Blue) && banner.focus_sizes[1] > 0
-            }
-            GreenFourstarFocus => {
-                banner.fourstar_focus == Some(Color::Green) && banner.focus_sizes[2] > 0
-            }
-            ColorlessFourstarFocus => {
-                banner.fourstar_focus == Some(Color::Colorless) && banner.focus_sizes[3] > 0
-            }
-        }
-    }
+/// The budget that a goal starts out with when switching to one, in orbs.
+pub const DEFAULT_BUDGET: u32 = 500;
 
-    /// Says whether or not the preset has only a single unit that counts for
-    /// completing the goal.
-    fn is_single_target(&self) -> bool {
-        use GoalPreset::*;
-        match self {
-            RedFocus
-            | BlueFocus
-            | GreenFocus
-            | ColorlessFocus
-            | RedFourstarFocus
-            | BlueFourstarFocus
-            | GreenFourstarFocus
-            | ColorlessFourstarFocus => true,
-            _ => false,
-        }
-    }
-}
-
-/// Whether the given goal is to achieve all of the goal parts or just a single one.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum GoalKind {
-    Any,
-    All,
-}
-
-/// A single unit that the goal is trying to obtain.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
-pub struct GoalPart {
-    pub unit_color: Color,
-    pub num_copies: u8,
-    pub four_star: bool,
-}
-
-/// The flexible representation of a goal
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct CustomGoal {
-    pub kind: GoalKind,
-    pub goals: Vec<GoalPart>,
-}
-
-/// The goal of a summoning session.
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub enum Goal {
-    Custom(CustomGoal),
-    Preset(GoalPreset, u8),
-}
-
-impl Default for Goal {
-    fn default() -> Self {
-        Goal::Preset(GoalPreset::AnyFocus, 1)
-    }
-}
-
-impl Goal {
-    /// Convert the current preset into a custom goal or retreive the current
-    /// custom goal.
-    pub fn as_custom(&self, banner: &Banner) -> CustomGoal {
-        use crate::goal::GoalKind::*;
-        use crate::goal::GoalPreset::*;
-        use crate::Color::*;
-
-        let (preset, count) = match self {
-            Goal::Preset(preset, count) => (*preset, *count),
-            Goal::Custom(custom) => return custom.clone(),
-        };
-
-        let count = if preset.is_single_target() {
-            count.max(1)
-        } else {
-            1
-        };
-
-        let kind = match preset {
-            AllFocus => All,
-            // Every other preset is either Any* or has only one target
-            _ => Any,
-        };
-        let mut custom_goal = CustomGoal {
-            kind,
-            goals: vec![],
-        };
-
-        let mut add_color_goal = |color: Color, four_star: bool| {
-            custom_goal.goals.push(GoalPart {
-                unit_color: color,
-                num_copies: count,
-                four_star,
-            });
-        };
-        // Add an individual GoalPart for each focus unit that matches the
-        // conditions of the overall goal.
-        match preset {
-            AllFocus | AnyFocus => {
-                for idx in 0..banner.focus_sizes.len() {
-                    for _ in 0..banner.focus_sizes[idx] {
-                        add_color_goal(Color::try_from(idx as u8).unwrap(), false);
-                    }
-                }
-            }
-            RedFocus => add_color_goal(Red, false),
-            BlueFocus => add_color_goal(Blue, false),
-            GreenFocus => add_color_goal(Green, false),
-            ColorlessFocus => add_color_goal(Colorless, false),
-            AnyRed => {
-                for _ in 0..banner.focus_sizes[0] {
-                    add_color_goal(Red, false)
-                }
-            }
-            AnyBlue => {
-                for _ in 0..banner.focus_sizes[1] {
-                    add_color_goal(Blue, false)
-                }
-            }
-            AnyGreen => {
-                for _ in 0..banner.focus_sizes[2] {
-                    add_color_goal(Green, false)
-                }
-            }
-            AnyColorless => {
-                for _ in 0..banner.focus_sizes[3] {
-                    add_color_goal(Colorless, false)
-                }
-            }
-            RedFourstarFocus => add_color_goal(Red, true),
-            BlueFourstarFocus => add_color_goal(Blue, true),
-            GreenFourstarFocus => add_color_goal(Green, true),
-            ColorlessFourstarFocus => add_color_goal(Colorless, true),
-        }
-
-        custom_goal
-    }
-
-    /// Checks whether or not the goal is possible on the given banner.
-    pub fn is_available(&self, banner: &Banner) -> bool {
-        match self {
-            Goal::Custom(custom_goal) => custom_goal
-                .goals
-                .iter()
-                .any(|&GoalPart { unit_color, .. }| banner.focus_sizes[unit_color as usize] > 0),
-            Goal::Preset(preset, _) => preset.is_available(banner),
-        }
-    }
-
-    /// Parses data from the representation used in query strings to share settings.
-    pub fn from_query_string(s: &str) -> Option<Self> {
-        let data = base64::decode(s).ok()?;
-        bincode::deserialize(&data).ok()
-    }
-}
+/// The number of copies of a unit that it takes to merge it to +10.
+const MERGE_COPIES: u16 = 11;
 
 /// Section for selecting the goal.
 pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
@@ -289,7 +64,7 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
         id!["goal_selector"],
         select,
         if let Goal::Preset(preset, count) = goal {
-            if preset.is_single_target() {
+            if preset.uses_count() {
                 span![
                     label![
                         attrs![
@@ -300,7 +75,7 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
                     input![
                         id!["goal_count"],
                         input_ev("input", |text| {
-                            if let Ok(quantity) = text.parse::<u8>() {
+                            if let Ok(quantity) = text.parse::<u16>() {
                                 Msg::GoalPresetQuantityChange { quantity }
                             } else {
                                 Msg::GoalPresetQuantityChange { quantity: 0 }
@@ -317,7 +92,20 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
                             At::Min => 1;
                             At::Required => true;
                         ],
-                    ]
+                    ],
+                    if preset.is_single_target() {
+                        button![
+                            simple_ev(
+                                Ev::Click,
+                                Msg::GoalPresetQuantityChange {
+                                    quantity: MERGE_COPIES
+                                }
+                            ),
+                            "+10 merge (11 copies)",
+                        ]
+                    } else {
+                        seed::empty()
+                    },
                 ]
             } else {
                 seed::empty()
@@ -325,51 +113,170 @@ pub fn goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
         } else {
             seed::empty()
         },
-        advanced_goal_selector(goal),
+        advanced_goal_selector(goal, banner),
+        goal.problems(banner)
+            .into_iter()
+            .map(|problem| p![class!["error"], problem])
+            .collect::<Vec<_>>(),
     ]
 }
 
 /// Subsection for selecting the goal using the detailed representation instead of
 /// a preset.
-fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
+fn advanced_goal_selector(goal: &Goal, banner: &Banner) -> Node<Msg> {
     if let Goal::Custom(custom_goal) = goal {
         let mut base = div![style!["margin-left" => "2em";]];
-        if custom_goal.goals.len() > 1 {
-            base.add_child(select![
-                input_ev(Ev::Input, |text| match &*text {
-                    "Any" => Msg::GoalKindChange {
-                        kind: GoalKind::Any
-                    },
-                    "All" => Msg::GoalKindChange {
-                        kind: GoalKind::All
-                    },
-                    _ => Msg::Null,
-                }),
-                option![
+        let budget = match custom_goal.kind {
+            GoalKind::Budget(orbs) => Some(orbs),
+            _ => None,
+        };
+        let total = match custom_goal.kind {
+            GoalKind::Total(copies) => Some(copies),
+            _ => None,
+        };
+        let at_least = match custom_goal.kind {
+            GoalKind::AtLeast(parts) => Some(parts),
+            _ => None,
+        };
+        let expression = match &custom_goal.kind {
+            GoalKind::Expression(text) => Some(text.clone()),
+            _ => None,
+        };
+        // Switching to an expression starts out with one that needs all of
+        // the parts, to edit from there.
+        let all_parts = (1..=custom_goal.goals.len())
+            .map(|number| number.to_string())
+            .collect::<Vec<_>>()
+            .join(" and ");
+        let kind_option = |value: &str, label: &str, selected: bool| -> Node<Msg> {
+            let mut attrs = attrs![
+                At::Value => value;
+            ];
+            if selected {
+                attrs.add(At::Selected, "");
+            }
+            option![attrs, label]
+        };
+        base.add_child(select![
+            input_ev(Ev::Input, move |text| match &*text {
+                "Any" => Msg::GoalKindChange {
+                    kind: GoalKind::Any
+                },
+                "All" => Msg::GoalKindChange {
+                    kind: GoalKind::All
+                },
+                "EachColor" => Msg::GoalKindChange {
+                    kind: GoalKind::EachColor
+                },
+                "Budget" => Msg::GoalKindChange {
+                    kind: GoalKind::Budget(budget.unwrap_or(DEFAULT_BUDGET))
+                },
+                "Total" => Msg::GoalKindChange {
+                    kind: GoalKind::Total(total.unwrap_or(1))
+                },
+                "AtLeast" => Msg::GoalKindChange {
+                    kind: GoalKind::AtLeast(at_least.unwrap_or(1))
+                },
+                "Expression" => Msg::GoalKindChange {
+                    kind: GoalKind::Expression(all_parts.clone())
+                },
+                _ => Msg::Null,
+            }),
+            kind_option("Any", "Any of these", custom_goal.kind == GoalKind::Any),
+            kind_option("All", "All of these", custom_goal.kind == GoalKind::All),
+            kind_option(
+                "EachColor",
+                "One of these of each color",
+                custom_goal.kind == GoalKind::EachColor
+            ),
+            kind_option("Budget", "All of these within a budget", budget.is_some()),
+            kind_option("Total", "Copies in total of these", total.is_some()),
+            kind_option("AtLeast", "At least some of these", at_least.is_some()),
+            kind_option("Expression", "A combination of these", expression.is_some()),
+        ]);
+        if let Some(orbs) = budget {
+            base.add_child(span![
+                " of ",
+                input![
+                    input_ev(Ev::Input, |value| {
+                        if let Ok(orbs) = value.parse::<u32>() {
+                            Msg::GoalKindChange {
+                                kind: GoalKind::Budget(orbs),
+                            }
+                        } else {
+                            Msg::Null
+                        }
+                    }),
                     attrs![
-                        At::Value => "Any";
-                    ],
-                    if custom_goal.kind == GoalKind::Any {
-                        attrs![At::Selected => ""]
-                    } else {
-                        attrs![]
-                    },
-                    "Any of these",
+                        At::Type => "number";
+                        At::Min => 0;
+                        At::Required => true;
+                        At::Value => orbs;
+                    ]
                 ],
-                option![
+                " orbs",
+            ]);
+        }
+        if let Some(copies) = total {
+            base.add_child(span![
+                ": ",
+                input![
+                    input_ev(Ev::Input, |value| {
+                        if let Ok(copies) = value.parse::<u16>() {
+                            Msg::GoalKindChange {
+                                kind: GoalKind::Total(copies),
+                            }
+                        } else {
+                            Msg::Null
+                        }
+                    }),
                     attrs![
-                        At::Value => "All";
-                    ],
-                    if custom_goal.kind == GoalKind::All {
-                        attrs![At::Selected => ""]
-                    } else {
-                        attrs![]
-                    },
-                    "All of these",
+                        At::Type => "number";
+                        At::Class => "small_number";
+                        At::Min => 1;
+                        At::Required => true;
+                        At::Value => copies;
+                    ]
+                ],
+            ]);
+        }
+
+        if let Some(parts) = at_least {
+            base.add_child(span![
+                ": ",
+                input![
+                    input_ev(Ev::Input, |value| {
+                        if let Ok(parts) = value.parse::<u8>() {
+                            Msg::GoalKindChange {
+                                kind: GoalKind::AtLeast(parts),
+                            }
+                        } else {
+                            Msg::Null
+                        }
+                    }),
+                    attrs![
+                        At::Type => "number";
+                        At::Class => "small_number";
+                        At::Min => 1;
+                        At::Required => true;
+                        At::Value => parts;
+                    ]
                 ],
             ]);
         }
 
+        if let Some(text) = &expression {
+            base.add_child(div![input![
+                input_ev(Ev::Input, |text| Msg::GoalKindChange {
+                    kind: GoalKind::Expression(text),
+                }),
+                attrs![
+                    At::Placeholder => "1 and (2 or 3)";
+                    At::Value => text;
+                ]
+            ]]);
+        }
+
         for (index, goal_part) in custom_goal.goals.iter().enumerate() {
             let mut color_select = select![input_ev(Ev::Input, move |value| {
                 if let Some(color) = value
@@ -389,6 +296,28 @@ fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
                 }
                 color_select.add_child(option![attrs, color.to_string()]);
             }
+            let mut pool_select = select![input_ev(Ev::Input, move |value| {
+                if let Some(pool) = value
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(|num| Pool::try_from(num).ok())
+                {
+                    Msg::GoalPartPoolChange { index, pool }
+                } else {
+                    Msg::Null
+                }
+            }),];
+            for &(pool, label) in &[
+                (Pool::Focus, "focus unit"),
+                (Pool::Fivestar, "off-focus 5* unit"),
+                (Pool::FourstarSpecial, "4* special rate unit"),
+            ] {
+                let mut attrs = attrs![At::Value => pool as usize];
+                if goal_part.pool == pool {
+                    attrs.add(At::Selected, "");
+                }
+                pool_select.add_child(option![attrs, label]);
+            }
             base.add_child(div![
                 button![
                     simple_ev(
@@ -397,9 +326,15 @@ fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
                     ),
                     "X",
                 ],
+                // Expressions refer to the parts by number.
+                if expression.is_some() {
+                    span![format!(" {}. ", index + 1)]
+                } else {
+                    seed::empty()
+                },
                 input![
                     input_ev(Ev::Input, move |value| {
-                        if let Ok(quantity) = value.parse::<u8>() {
+                        if let Ok(quantity) = value.parse::<u16>() {
                             Msg::GoalPartQuantityChange { index, quantity }
                         } else {
                             Msg::Null
@@ -415,7 +350,22 @@ fn advanced_goal_selector(goal: &Goal) -> Node<Msg> {
                 ],
                 " copies of a specific ",
                 color_select,
-                " unit",
+                " ",
+                pool_select,
+                match banner.pool_size(goal_part.pool, goal_part.unit_color) {
+                    0 => " (none on this banner)".into(),
+                    count => match goal_part.pool {
+                        Pool::Focus => banner
+                            .focus_name(goal_part.unit_color, custom_goal.unit_index(index))
+                            .map(|name| format!(" ({})", name)),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| format!(" (1 of {})", count)),
+                },
+                match banner.unit_chance(goal_part.pool, goal_part.unit_color) {
+                    Some(chance) => format!(", {:.2}% per summon", chance),
+                    None => String::new(),
+                },
             ]);
         }
 
@@ -0,0 +1,24 @@
+use seed::prelude::*;
+
+use crate::Msg;
+
+/// Section listing what has to be changed before the settings can be run. It
+/// stays hidden after being dismissed until the list changes.
+pub fn issues_panel(issues: &[String], dismissed: &[String]) -> Node<Msg> {
+    if issues.is_empty() || issues == dismissed {
+        return seed::empty();
+    }
+    div![
+        id!["issues"],
+        class!["error"],
+        p![
+            "These settings can't be run yet:",
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::IssuesDismiss),
+                "Dismiss",
+            ],
+        ],
+        ul![issues.iter().map(|issue| li![issue]).collect::<Vec<_>>()],
+    ]
+}
@@ -0,0 +1,135 @@
+//! Estimating what a number of orbs costs in real money, from the prices of
+//! the orb packs sold in the game's store.
+
+use seed::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Msg;
+
+/// One of the orb packs sold in the store.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OrbPack {
+    pub orbs: u32,
+    pub price: f32,
+}
+
+/// The orb packs that can be bought, and what their prices are in.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OrbPrices {
+    /// Written before each price, like "$".
+    pub currency: String,
+    pub packs: Vec<OrbPack>,
+}
+
+impl Default for OrbPrices {
+    /// Roughly the prices in the US store.
+    fn default() -> Self {
+        let pack = |orbs, price| OrbPack { orbs, price };
+        OrbPrices {
+            currency: "$".into(),
+            packs: vec![
+                pack(3, 1.99),
+                pack(10, 6.99),
+                pack(23, 15.99),
+                pack(48, 32.99),
+                pack(75, 48.99),
+                pack(110, 66.99),
+                pack(140, 74.99),
+            ],
+        }
+    }
+}
+
+impl OrbPrices {
+    /// The cheapest way to buy at least each number of orbs from 0 up to
+    /// `max`, or `None` if there aren't any packs to buy.
+    pub fn costs(&self, max: u32) -> Option<Vec<f32>> {
+        let packs = self
+            .packs
+            .iter()
+            .filter(|pack| pack.orbs > 0 && pack.price >= 0.0)
+            .collect::<Vec<_>>();
+        if packs.is_empty() {
+            return None;
+        }
+        let mut costs = vec![0.0; max as usize + 1];
+        for orbs in 1..costs.len() {
+            costs[orbs] = packs
+                .iter()
+                .map(|pack| costs[orbs.saturating_sub(pack.orbs as usize)] + pack.price)
+                .fold(f32::INFINITY, f32::min);
+        }
+        Some(costs)
+    }
+
+    /// A price for display, in the chosen currency.
+    pub fn format(&self, price: f32) -> String {
+        format!("{}{:.2}", self.currency, price)
+    }
+}
+
+/// Section for editing the orb pack prices.
+pub fn prices_editor(prices: &OrbPrices) -> Node<Msg> {
+    let mut table = table![tr![th![], th!["Orbs"], th!["Price"]]];
+    for (index, pack) in prices.packs.iter().enumerate() {
+        table.add_child(tr![
+            td![button![
+                simple_ev(Ev::Click, Msg::OrbPackRemove { index }),
+                "X",
+            ]],
+            td![input![
+                input_ev(Ev::Input, move |text| match text.parse::<u32>() {
+                    Ok(orbs) => Msg::OrbPackOrbsChange { index, orbs },
+                    Err(_) => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Min => 1;
+                    At::Value => pack.orbs;
+                ],
+            ]],
+            td![input![
+                input_ev(Ev::Input, move |text| match text.parse::<f32>() {
+                    Ok(price) if price >= 0.0 => Msg::OrbPackPriceChange { index, price },
+                    _ => Msg::Null,
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Min => 0;
+                    At::Step => "any";
+                    At::Value => pack.price;
+                ],
+            ]],
+        ]);
+    }
+    details![
+        id!["orb_prices"],
+        summary!["Orb prices"],
+        div![
+            label![
+                attrs![
+                    At::For => "orb_currency";
+                ],
+                "Currency: ",
+            ],
+            input![
+                id!["orb_currency"],
+                input_ev(Ev::Input, |currency| Msg::OrbCurrencyChange { currency }),
+                attrs![
+                    At::Class => "small_number";
+                    At::Value => prices.currency;
+                ],
+            ],
+        ],
+        table,
+        button![simple_ev(Ev::Click, Msg::OrbPackAdd), "+"],
+        button![
+            class!["padleft"],
+            simple_ev(Ev::Click, Msg::OrbPricesReset),
+            "Back to the usual prices",
+        ],
+    ]
+}
@@ -0,0 +1,80 @@
+use seed::prelude::*;
+
+use fehsim_core::banner::Banner;
+use fehsim_core::goal::Goal;
+
+use crate::Msg;
+
+/// Banners to summon on before the current one, each with its own goal, where
+/// the orbs for all of them come out of one shared budget. The current banner
+/// and goal are the plan's last step.
+#[derive(Clone, Debug)]
+pub struct Plan {
+    pub steps: Vec<(Banner, Goal)>,
+    /// The orbs available for the whole plan.
+    pub budget: u32,
+}
+
+/// Section for summoning on several banners in a row.
+pub fn plan_selector(plan: Option<&Plan>, can_add: bool) -> Node<Msg> {
+    let mut base = div![id!["plan_selector"]];
+    if let Some(plan) = plan {
+        for (index, (banner, goal)) in plan.steps.iter().enumerate() {
+            base.add_child(div![
+                button![simple_ev(Ev::Click, Msg::PlanRemoveStep { index }), "X",],
+                match &banner.name {
+                    Some(name) => format!(" Step {}: {}, {}", index + 1, name, goal_label(goal)),
+                    None => format!(" Step {}: {}", index + 1, goal_label(goal)),
+                },
+            ]);
+        }
+        base.add_child(div![format!(
+            "Step {}: the banner and goal above",
+            plan.steps.len() + 1
+        )]);
+        base.add_child(div![
+            label![
+                attrs![
+                    At::For => "plan_budget";
+                ],
+                "Orbs for the whole plan: ",
+            ],
+            input![
+                id!["plan_budget"],
+                input_ev(Ev::Input, |value| {
+                    if let Ok(orbs) = value.parse::<u32>() {
+                        Msg::PlanBudgetChange { orbs }
+                    } else {
+                        Msg::Null
+                    }
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Min => 0;
+                    At::Required => true;
+                    At::Value => plan.budget;
+                ],
+            ],
+        ]);
+    }
+    base.add_child(button![
+        simple_ev(Ev::Click, Msg::PlanAddStep),
+        if !can_add {
+            attrs![At::Disabled => true]
+        } else {
+            attrs![]
+        },
+        "Summon on another banner after this one",
+    ]);
+    base
+}
+
+/// Short description of a plan step's goal.
+pub fn goal_label(goal: &Goal) -> String {
+    match goal {
+        Goal::Preset(preset, count) if preset.uses_count() => format!("{} ({})", preset, count),
+        Goal::Preset(preset, _) => preset.to_string(),
+        Goal::Custom(custom) if custom.goals.len() == 1 => "Custom goal with 1 target".into(),
+        Goal::Custom(custom) => format!("Custom goal with {} targets", custom.goals.len()),
+    }
+}
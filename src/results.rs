@@ -1,13 +1,1220 @@
 use seed::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::counter::Counter;
+use fehsim_core::banner::Banner;
+use fehsim_core::counter::Counter;
+use fehsim_core::goal::CustomGoal;
+use fehsim_core::sim::RunResult;
+use fehsim_core::stats;
+use fehsim_core::strategy::Strategy;
+use fehsim_core::Pool;
+
+use crate::money::{self, OrbPrices};
 use crate::Msg;
 
+mod cdf;
+pub mod charts;
+mod histogram;
 mod svg_graph;
 
+pub use cdf::PinnedCurve;
+
+/// Distributions of things that happened along the way to reaching the goal,
+/// with one entry per run.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SideStats {
+    /// The number of summons made.
+    pub summons: Counter,
+    /// The number of summoning circles that summons were made from.
+    pub circles: Counter,
+    /// The number of summons that didn't cost any orbs.
+    pub free_summons: Counter,
+    /// The number of 4* special rate units obtained.
+    pub fourstar_specials: Counter,
+    /// The number of off-focus 5* units obtained.
+    pub pitybreakers: Counter,
+    /// The number of 5* focus units summoned, not counting sparks.
+    pub focus_units: Counter,
+    /// The number of 4* units summoned.
+    pub fourstars: Counter,
+    /// The number of 5* units summoned, focus and off-focus together.
+    pub fivestars: Counter,
+    /// The number of summons made only because a circle had nothing to open.
+    pub forced_summons: Counter,
+    /// The orbs spent on those summons.
+    pub forced_orbs: Counter,
+    /// The number of sparks claimed.
+    pub sparks_claimed: Counter,
+    /// The number of runs that stopped without reaching the goal. They aren't
+    /// counted anywhere else, since they didn't reach it.
+    pub unfinished_runs: u32,
+    /// The number of runs that reached the goal only as soon as they did
+    /// because a spark gave a unit that it needed.
+    pub needed_spark_runs: u32,
+    /// The total number of summoning circles across all runs, indexed by how
+    /// many stones of a color that was still needed they had.
+    pub circles_by_target_stones: [u64; 6],
+    /// The number of orbs spent by the time each goal part was finished, for
+    /// goals with more than one part. Runs that didn't finish a part aren't
+    /// counted for it.
+    pub part_orbs: Vec<Counter>,
+    /// The number of runs that each goal part was the last one finished in.
+    pub last_parts: Vec<u32>,
+}
+
+impl SideStats {
+    /// Adds the results of a single run.
+    pub fn record(&mut self, result: &RunResult) {
+        if !result.reached_goal {
+            self.unfinished_runs += 1;
+            return;
+        }
+        if result.needed_spark {
+            self.needed_spark_runs += 1;
+        }
+        self.summons[result.summons] += 1;
+        self.free_summons[result.free_summons] += 1;
+        self.fourstar_specials[result.fourstar_specials] += 1;
+        self.pitybreakers[result.pitybreakers] += 1;
+        self.focus_units[result.focus_units] += 1;
+        self.fourstars[result.fourstars] += 1;
+        self.fivestars[result.focus_units + result.pitybreakers] += 1;
+        self.circles[result.circles] += 1;
+        self.forced_summons[result.forced_summons] += 1;
+        self.forced_orbs[result.forced_orbs] += 1;
+        self.sparks_claimed[result.sparks_claimed] += 1;
+        for (total, &count) in self
+            .circles_by_target_stones
+            .iter_mut()
+            .zip(result.circles_by_target_stones.iter())
+        {
+            *total += count as u64;
+        }
+        if self.part_orbs.len() < result.part_orbs.len() {
+            self.part_orbs
+                .resize(result.part_orbs.len(), Counter::default());
+        }
+        for (counter, orbs) in self.part_orbs.iter_mut().zip(result.part_orbs.iter()) {
+            if let Some(orbs) = orbs {
+                counter[*orbs] += 1;
+            }
+        }
+        if let Some(part) = result.last_part {
+            if self.last_parts.len() <= part {
+                self.last_parts.resize(part + 1, 0);
+            }
+            self.last_parts[part] += 1;
+        }
+    }
+
+    /// Adds the runs recorded in another set of statistics.
+    pub fn merge(&mut self, other: &SideStats) {
+        self.summons.merge(&other.summons);
+        self.free_summons.merge(&other.free_summons);
+        self.fourstar_specials.merge(&other.fourstar_specials);
+        self.pitybreakers.merge(&other.pitybreakers);
+        self.focus_units.merge(&other.focus_units);
+        self.fourstars.merge(&other.fourstars);
+        self.fivestars.merge(&other.fivestars);
+        self.circles.merge(&other.circles);
+        self.forced_summons.merge(&other.forced_summons);
+        self.forced_orbs.merge(&other.forced_orbs);
+        self.sparks_claimed.merge(&other.sparks_claimed);
+        self.unfinished_runs += other.unfinished_runs;
+        self.needed_spark_runs += other.needed_spark_runs;
+        for (total, &count) in self
+            .circles_by_target_stones
+            .iter_mut()
+            .zip(other.circles_by_target_stones.iter())
+        {
+            *total += count;
+        }
+        if self.part_orbs.len() < other.part_orbs.len() {
+            self.part_orbs
+                .resize(other.part_orbs.len(), Counter::default());
+        }
+        for (counter, other) in self.part_orbs.iter_mut().zip(other.part_orbs.iter()) {
+            counter.merge(other);
+        }
+        if self.last_parts.len() < other.last_parts.len() {
+            self.last_parts.resize(other.last_parts.len(), 0);
+        }
+        for (total, &count) in self.last_parts.iter_mut().zip(other.last_parts.iter()) {
+            *total += count;
+        }
+    }
+
+    /// The number of runs recorded, counting the ones in `data` that reached
+    /// the goal along with the ones that didn't.
+    pub fn runs(&self, data: &Counter) -> u32 {
+        data.iter().sum::<u32>() + self.unfinished_runs
+    }
+
+    /// Throws away all of the recorded runs.
+    pub fn clear(&mut self) {
+        self.summons.clear();
+        self.free_summons.clear();
+        self.fourstar_specials.clear();
+        self.pitybreakers.clear();
+        self.focus_units.clear();
+        self.fourstars.clear();
+        self.fivestars.clear();
+        self.circles.clear();
+        self.forced_summons.clear();
+        self.forced_orbs.clear();
+        self.sparks_claimed.clear();
+        self.unfinished_runs = 0;
+        self.needed_spark_runs = 0;
+        self.circles_by_target_stones = [0; 6];
+        self.part_orbs.clear();
+        self.last_parts.clear();
+    }
+}
+
+/// Differences in the orbs spent between paired runs of some saved settings
+/// and the current ones.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    runs: u32,
+    total_difference: f64,
+    total_squared_difference: f64,
+    /// The number of pairs where the current settings took fewer orbs.
+    fewer: u32,
+    /// The number of pairs where the current settings took more orbs.
+    more: u32,
+}
+
+impl Comparison {
+    /// Adds the results of a pair of runs.
+    pub fn record(&mut self, baseline: &RunResult, current: &RunResult) {
+        let difference = current.orbs as f64 - baseline.orbs as f64;
+        self.runs += 1;
+        self.total_difference += difference;
+        self.total_squared_difference += difference * difference;
+        if difference < 0.0 {
+            self.fewer += 1;
+        } else if difference > 0.0 {
+            self.more += 1;
+        }
+    }
+
+    /// Adds the pairs recorded in another comparison.
+    pub fn merge(&mut self, other: &Comparison) {
+        self.runs += other.runs;
+        self.total_difference += other.total_difference;
+        self.total_squared_difference += other.total_squared_difference;
+        self.fewer += other.fewer;
+        self.more += other.more;
+    }
+
+    /// Throws away all of the recorded runs.
+    pub fn clear(&mut self) {
+        *self = Comparison::default();
+    }
+}
+
+/// The percentiles that need to be precise for the results to count as
+/// converged.
+const CONVERGENCE_PERCENTILES: [f32; 3] = [0.5, 0.9, 0.99];
+
+/// How precise each of those percentiles needs to be, as a standard error
+/// relative to the percentile itself.
+const CONVERGENCE_THRESHOLD: f32 = 0.005;
+
+/// The fewest runs that the standard errors can be trusted for, and that the
+/// results can be trusted at all with.
+const MIN_TRUSTED_RUNS: u32 = 1000;
+
+/// The largest standard error of the median, relative to the median itself,
+/// that the results are trusted with.
+const MAX_TRUSTED_MEDIAN_ERROR: f32 = 0.02;
+
+/// Estimates how close the results are to converging, where 1.0 or more
+/// means converged. Since the standard error shrinks with the square root of
+/// the number of runs, a value of 0.5 means that about twice as many runs are
+/// needed.
+pub fn convergence(data: &Counter) -> f32 {
+    if data.iter().sum::<u32>() < MIN_TRUSTED_RUNS {
+        return 0.0;
+    }
+    CONVERGENCE_PERCENTILES
+        .iter()
+        .map(|&pct| {
+            let target = (stats::percentile(data, pct) as f32 * CONVERGENCE_THRESHOLD).max(1.0);
+            let error = stats::percentile_standard_error(data, pct);
+            (target / error).powi(2)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// When a run in progress stops.
+#[derive(Copy, Clone, Debug)]
+pub enum StopRule {
+    /// After the given number of milliseconds.
+    Time(f64),
+    /// After the given number of runs.
+    Runs(u32),
+    /// Once the results have converged.
+    Converged,
+}
+
+impl Default for StopRule {
+    fn default() -> Self {
+        StopRule::Time(0.0)
+    }
+}
+
+/// How far along a run in progress is.
+#[derive(Default, Debug)]
+pub struct Progress {
+    /// The number of runs finished since the run started.
+    pub runs: u32,
+    /// The time spent so far, in milliseconds.
+    pub elapsed: f64,
+    /// How close all of the results so far are to converging, as given by
+    /// `convergence`.
+    pub convergence: f32,
+    pub stop_rule: StopRule,
+}
+
+impl Progress {
+    /// Whether the run has gone on long enough.
+    pub fn is_finished(&self) -> bool {
+        self.fraction() >= 1.0
+    }
+
+    /// The most runs left to simulate.
+    pub fn runs_left(&self) -> u32 {
+        match self.stop_rule {
+            StopRule::Runs(target_runs) => target_runs.saturating_sub(self.runs),
+            _ => u32::MAX,
+        }
+    }
+
+    /// Roughly how much of the run is done, from 0.0 to 1.0 or over.
+    fn fraction(&self) -> f64 {
+        match self.stop_rule {
+            StopRule::Time(duration) => self.elapsed / duration,
+            StopRule::Runs(target_runs) => self.runs as f64 / target_runs as f64,
+            StopRule::Converged => self.convergence as f64,
+        }
+    }
+}
+
+/// The percentiles that the results show until others are chosen.
+pub const DEFAULT_PERCENTILES: [f32; 5] = [0.25, 0.5, 0.75, 0.9, 0.99];
+
+/// What the results are counted in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Measure {
+    Orbs,
+    Summons,
+}
+
+impl Default for Measure {
+    fn default() -> Self {
+        Measure::Orbs
+    }
+}
+
+impl Measure {
+    /// The name of the unit, in lowercase.
+    pub fn unit(self) -> &'static str {
+        match self {
+            Measure::Orbs => "orbs",
+            Measure::Summons => "summons",
+        }
+    }
+}
+
+/// The results counted in the chosen `measure`, along with the measure that
+/// they're actually counted in, since exact results only know the orbs.
+pub fn shown_data<'a>(
+    data: &'a Counter,
+    side_stats: &'a SideStats,
+    measure: Measure,
+) -> (&'a Counter, Measure) {
+    match measure {
+        Measure::Summons if !side_stats.summons.is_empty() => {
+            (&side_stats.summons, Measure::Summons)
+        }
+        _ => (data, Measure::Orbs),
+    }
+}
+
 /// Section for displaying the results. If `highlight` is given, places a label
-/// on the graph at the specified point. Otherwise, labels are placed at pre-set
-/// locations.
-pub fn results(data: &Counter, highlight: Option<f32>) -> Node<Msg> {
-    div![id!["results"], svg_graph::graph(data, highlight),]
+/// on the graph at the specified point. Otherwise, labels are placed at the
+/// chosen `percentiles`, which are sorted. If `progress` is given, shows how
+/// far along the current run is. If the goal has a `budget`, shows the chance
+/// of reaching it within the budget instead of the graph. If the results are
+/// `stale`, they're shown faded, without ways to save or pin them. The results are
+/// counted in the `measure` that's chosen, where it's known.
+pub fn results(
+    data: &Counter,
+    side_stats: &SideStats,
+    banner: &Banner,
+    strategy: &Strategy,
+    goal: &CustomGoal,
+    budget: Option<u32>,
+    comparison: &Comparison,
+    rng_seed: Option<u64>,
+    converged: bool,
+    highlight: Option<f32>,
+    percentiles: &[f32],
+    lookup_chance: Option<f32>,
+    lookup_orbs: Option<u32>,
+    luck_orbs: Option<u32>,
+    bin_width: Option<u32>,
+    pinned: &[PinnedCurve],
+    cdf_hover: Option<f32>,
+    log_scale: bool,
+    measure: Measure,
+    orb_prices: &OrbPrices,
+    summary_copied: bool,
+    stale: bool,
+    progress: Option<&Progress>,
+) -> Node<Msg> {
+    let can_count_summons = !side_stats.summons.is_empty();
+    let (shown, measure) = shown_data(data, side_stats, measure);
+    let unit = measure.unit();
+    div![
+        id!["results"],
+        if stale {
+            p![
+                class!["error"],
+                "The settings have changed since these results were simulated. Press \"Run\" to simulate the new settings.",
+            ]
+        } else {
+            seed::empty()
+        },
+        if stale { class!["stale"] } else { class![] },
+        match progress {
+            Some(progress) => progress_summary(progress),
+            None => seed::empty(),
+        },
+        match budget {
+            Some(budget) => budget_summary(data, side_stats, budget),
+            None => div![
+                unfinished_summary(data, side_stats),
+                if can_count_summons {
+                    measure_selector(measure)
+                } else {
+                    seed::empty()
+                },
+                overall_summary(shown, unit),
+                svg_graph::graph(shown, highlight, percentiles, unit),
+                charts::download_button("graph"),
+                percentile_table(
+                    shown,
+                    percentiles,
+                    unit,
+                    if measure == Measure::Orbs {
+                        Some(orb_prices)
+                    } else {
+                        None
+                    },
+                ),
+                if measure == Measure::Orbs {
+                    money::prices_editor(orb_prices)
+                } else {
+                    seed::empty()
+                },
+                lookup_section(shown, lookup_chance, lookup_orbs, unit),
+                luck_checker(shown, luck_orbs, unit),
+                histogram_section(shown, bin_width, log_scale, unit),
+                cdf::cdf_chart(
+                    shown,
+                    pinned,
+                    measure,
+                    cdf_hover,
+                    lookup_orbs,
+                    log_scale,
+                    !stale
+                ),
+                pinned_table(pinned, measure),
+            ],
+        },
+        // Exact results are exact no matter how many runs they'd be worth.
+        if progress.is_none() && can_count_summons {
+            sample_summary(shown, unit)
+        } else {
+            seed::empty()
+        },
+        match rng_seed {
+            Some(rng_seed) if !data.is_empty() => p![format!("Seed: {}", rng_seed)],
+            _ => seed::empty(),
+        },
+        if converged {
+            p!["Converged: the 50th, 90th, and 99th percentiles each have a standard error of at most 0.5%."]
+        } else {
+            seed::empty()
+        },
+        comparison_summary(comparison),
+        // Exact results don't come with any side statistics.
+        if !side_stats.summons.is_empty() {
+            side_stats_summary(data, side_stats, banner, strategy)
+        } else {
+            seed::empty()
+        },
+        yield_summary(side_stats),
+        part_summary(data, side_stats, banner, goal),
+        if !data.is_empty() {
+            circle_summary(&side_stats.circles_by_target_stones)
+        } else {
+            seed::empty()
+        },
+        if !data.is_empty() && !stale {
+            div![
+                id!["export"],
+                button![simple_ev(Ev::Click, Msg::ExportCsv), "Export CSV"],
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::ExportJson),
+                    "Export JSON"
+                ],
+                button![
+                    class!["padleft"],
+                    simple_ev(Ev::Click, Msg::SummaryCopy),
+                    attrs![
+                        At::Title => "Copy a table of the results, formatted for Reddit and Discord";
+                    ],
+                    "Copy summary"
+                ],
+                if summary_copied {
+                    span![class!["padleft"], "Copied!"]
+                } else {
+                    seed::empty()
+                },
+            ]
+        } else {
+            seed::empty()
+        },
+    ]
+}
+
+/// Progress bar for the current run, along with how quickly it's going.
+fn progress_summary(progress: &Progress) -> Node<Msg> {
+    let runs_per_second = if progress.elapsed > 0.0 {
+        progress.runs as f64 / progress.elapsed * 1000.0
+    } else {
+        0.0
+    };
+    div![
+        id!["progress"],
+        progress![attrs![
+            At::Max => 1;
+            At::Value => progress.fraction().min(1.0);
+        ]],
+        span![
+            class!["padleft"],
+            format!(
+                "{} runs so far, {:.0} per second",
+                progress.runs, runs_per_second
+            ),
+        ],
+    ]
+}
+
+/// The number of runs simulated and how precise the median is, with a warning
+/// if there are too few runs to trust the results.
+fn sample_summary(data: &Counter, unit: &str) -> Node<Msg> {
+    let runs: u32 = data.iter().sum();
+    if runs == 0 {
+        return seed::empty();
+    }
+    let median = stats::percentile(data, 0.5);
+    let error = stats::percentile_standard_error(data, 0.5);
+    let trusted = runs >= MIN_TRUSTED_RUNS && error <= median as f32 * MAX_TRUSTED_MEDIAN_ERROR;
+    div![
+        id!["sample_summary"],
+        p![if runs >= MIN_TRUSTED_RUNS {
+            format!(
+                "{} runs, standard error of the median: {:.1} {}",
+                runs, error, unit
+            )
+        } else {
+            format!("{} runs", runs)
+        }],
+        if trusted {
+            seed::empty()
+        } else {
+            p![
+                class!["error"],
+                "Too few runs were simulated for these results to be trusted. Press \"More\" to simulate more of them, or choose a higher precision.",
+            ]
+        },
+    ]
+}
+
+/// The chance of reaching the goal within the budget, with a margin of error.
+fn budget_summary(data: &Counter, side_stats: &SideStats, budget: u32) -> Node<Msg> {
+    let total = side_stats.runs(data);
+    if total == 0 {
+        return seed::empty();
+    }
+    let runs = total as f64;
+    let chance = (total - side_stats.unfinished_runs) as f64 / runs;
+    // Margin of error for a 95% confidence interval.
+    let margin = 1.96 * (chance * (1.0 - chance) / runs).sqrt();
+    p![
+        id!["budget_summary"],
+        format!(
+            "Chance of reaching the goal within {} orbs: {:.1}% (±{:.1}%)",
+            budget,
+            100.0 * chance,
+            100.0 * margin,
+        ),
+    ]
+}
+
+/// How many runs stopped without reaching the goal, which the results leave
+/// out.
+fn unfinished_summary(data: &Counter, side_stats: &SideStats) -> Node<Msg> {
+    if side_stats.unfinished_runs == 0 {
+        return seed::empty();
+    }
+    p![
+        id!["unfinished_summary"],
+        format!(
+            "{:.1}% of runs stopped without reaching the goal. The results below are for the runs that reached it.",
+            100.0 * side_stats.unfinished_runs as f64 / side_stats.runs(data) as f64
+        ),
+    ]
+}
+
+/// Choice of whether the results are counted in orbs or summons.
+fn measure_selector(measure: Measure) -> Node<Msg> {
+    let option = |value: Measure, label: &str| {
+        let id = format!("measure_{}", value.unit());
+        span![
+            input![
+                id![&id],
+                simple_ev(Ev::Input, Msg::MeasureChange { measure: value }),
+                attrs![
+                    At::Type => "radio";
+                    At::Name => "measure";
+                    At::Checked => (measure == value).as_at_value();
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => id;
+                ],
+                label,
+            ],
+        ]
+    };
+    div![
+        id!["measure_selector"],
+        "Count the results in: ",
+        option(Measure::Orbs, "Orbs"),
+        option(Measure::Summons, "Summons"),
+    ]
+}
+
+/// The headline numbers of how many orbs or summons it took to reach the goal.
+fn overall_summary(data: &Counter, unit: &str) -> Node<Msg> {
+    match (stats::min(data), stats::max(data)) {
+        (Some(min), Some(max)) => p![
+            id!["overall_summary"],
+            format!(
+                "Average: {:.1} {}, median: {}, standard deviation: {:.1}, fewest: {}, most: {}",
+                stats::mean(data),
+                unit,
+                stats::percentile(data, 0.5),
+                stats::standard_deviation(data),
+                min,
+                max,
+            ),
+        ],
+        _ => seed::empty(),
+    }
+}
+
+/// Table of the number of orbs or summons that each chosen percentage of runs
+/// took, with controls for choosing the percentages. With `prices`, also
+/// shows what buying that many orbs would cost.
+fn percentile_table(
+    data: &Counter,
+    percentiles: &[f32],
+    unit: &str,
+    prices: Option<&OrbPrices>,
+) -> Node<Msg> {
+    if data.is_empty() {
+        return seed::empty();
+    }
+    let costs = prices.and_then(|prices| prices.costs(stats::max(data).unwrap_or(0)));
+    let mut table = table![
+        id!["percentile_table"],
+        tr![
+            th![],
+            th!["Chance"],
+            th![capitalized(unit)],
+            th!["95% range"],
+            if costs.is_some() {
+                th!["Cost"]
+            } else {
+                seed::empty()
+            },
+        ],
+    ];
+    if !percentiles.is_empty() {
+        for (index, orbs) in stats::percentiles(data, percentiles)
+            .into_iter()
+            .enumerate()
+        {
+            table.add_child(tr![
+                td![button![
+                    simple_ev(Ev::Click, Msg::PercentileRemove { index }),
+                    "X",
+                ]],
+                td![format!("{}%", percent_label(percentiles[index]))],
+                td![orbs.to_string()],
+                match stats::percentile_interval(data, percentiles[index]) {
+                    (low, high) if low == high => td![low.to_string()],
+                    (low, high) => td![format!("{}–{}", low, high)],
+                },
+                match (prices, &costs) {
+                    (Some(prices), Some(costs)) => td![prices.format(costs[orbs as usize])],
+                    _ => seed::empty(),
+                },
+            ]);
+        }
+    }
+    div![
+        table,
+        label![
+            attrs![
+                At::For => "percentile_add";
+            ],
+            "Add a percentage: ",
+        ],
+        input![
+            id!["percentile_add"],
+            input_ev(Ev::Change, |text| {
+                match text.trim().trim_end_matches('%').parse::<f32>() {
+                    Ok(percent) if percent > 0.0 && percent <= 100.0 => Msg::PercentileAdd {
+                        pct: percent / 100.0,
+                    },
+                    _ => Msg::Null,
+                }
+            }),
+            attrs![
+                At::Type => "number";
+                At::Class => "small_number";
+                At::Min => 0;
+                At::Max => 100;
+                At::Step => "any";
+                At::Placeholder => "99.9";
+            ],
+        ],
+        button![
+            class!["padleft"],
+            simple_ev(Ev::Click, Msg::PercentilesReset),
+            "Back to the usual percentages",
+        ],
+    ]
+}
+
+/// Lookups of how many orbs it takes to reach the goal with a chosen chance,
+/// for chances that aren't in the table, and of the chance of reaching it
+/// with a chosen number of orbs.
+fn lookup_section(
+    data: &Counter,
+    lookup_chance: Option<f32>,
+    lookup_orbs: Option<u32>,
+    unit: &str,
+) -> Node<Msg> {
+    if data.is_empty() {
+        return seed::empty();
+    }
+    div![
+        id!["lookup"],
+        div![lookup_chance_row(data, lookup_chance, unit)],
+        div![
+            label![
+                attrs![
+                    At::For => "lookup_orbs";
+                ],
+                "Chance of reaching the goal with ",
+            ],
+            input![
+                id!["lookup_orbs"],
+                input_ev(Ev::Input, |text| Msg::LookupOrbsChange {
+                    orbs: text.parse::<u32>().ok(),
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Min => 0;
+                    At::Value => lookup_orbs.map(|orbs| orbs.to_string()).unwrap_or_default();
+                ],
+            ],
+            format!(" {}: ", unit),
+            match lookup_orbs {
+                Some(orbs) => span![
+                    class!["highlight"],
+                    format!("{:.1}%", 100.0 * stats::fraction_at_most(data, orbs)),
+                ],
+                None => span!["-"],
+            },
+        ],
+    ]
+}
+
+/// The parts of the lookup of orbs needed for a chance of success.
+fn lookup_chance_row(data: &Counter, lookup_chance: Option<f32>, unit: &str) -> Vec<Node<Msg>> {
+    nodes![
+        label![
+            attrs![
+                At::For => "lookup_chance";
+            ],
+            format!("{} needed for a ", capitalized(unit)),
+        ],
+        input![
+            id!["lookup_chance"],
+            input_ev(Ev::Input, |text| Msg::LookupChanceChange {
+                chance: text
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|&percent| percent > 0.0 && percent <= 100.0)
+                    .map(|percent| percent / 100.0),
+            }),
+            attrs![
+                At::Type => "number";
+                At::Class => "small_number";
+                At::Min => 0;
+                At::Max => 100;
+                At::Step => "any";
+                At::Value => lookup_chance.map(percent_label).unwrap_or_default();
+            ],
+        ],
+        "% chance: ",
+        match lookup_chance {
+            Some(chance) => span![format!(
+                "{:.1} {}",
+                stats::interpolated_percentile(data, chance),
+                unit
+            )],
+            None => span!["-"],
+        },
+    ]
+}
+
+/// Where the number of orbs or summons that the goal actually took falls
+/// among the results.
+fn luck_checker(data: &Counter, luck_orbs: Option<u32>, unit: &str) -> Node<Msg> {
+    if data.is_empty() {
+        return seed::empty();
+    }
+    let verdict = luck_orbs.map(|orbs| {
+        let luckier_than = 1.0 - stats::fraction_at_most(data, orbs);
+        let unluckier_than = if orbs == 0 {
+            0.0
+        } else {
+            stats::fraction_at_most(data, orbs - 1)
+        };
+        format!(
+            "You were luckier than {:.1}% of runs, and unluckier than {:.1}%.",
+            100.0 * luckier_than,
+            100.0 * unluckier_than
+        )
+    });
+    div![
+        id!["luck_checker"],
+        label![
+            attrs![
+                At::For => "luck_orbs";
+            ],
+            format!("{} your goal actually took: ", capitalized(unit)),
+        ],
+        input![
+            id!["luck_orbs"],
+            input_ev(Ev::Input, |text| Msg::LuckOrbsChange {
+                orbs: text.parse::<u32>().ok(),
+            }),
+            attrs![
+                At::Type => "number";
+                At::Min => 0;
+                At::Value => luck_orbs.map(|orbs| orbs.to_string()).unwrap_or_default();
+            ],
+        ],
+        match verdict {
+            Some(verdict) => span![class!["padleft", "highlight"], verdict],
+            None => seed::empty(),
+        },
+    ]
+}
+
+/// Table comparing the pinned results side by side, with how far the median
+/// and 90th percentile of each are from those of the first one.
+fn pinned_table(pinned: &[PinnedCurve], measure: Measure) -> Node<Msg> {
+    if pinned.is_empty() {
+        return seed::empty();
+    }
+    let unit = measure.unit();
+    let key_percentiles = |data: &Counter| {
+        let values = stats::percentiles(data, &[0.5, 0.9]);
+        (values[0], values[1])
+    };
+    let first = pinned[0].counted_in(measure).map(key_percentiles);
+    let difference = |value: u32, first: u32| match value as i64 - first as i64 {
+        0 => "same".to_string(),
+        difference => format!("{:+}", difference),
+    };
+    let mut table = table![
+        id!["pinned_table"],
+        tr![
+            th!["Pinned results"],
+            th!["Runs"],
+            th![format!("Average {}", unit)],
+            th!["Median"],
+            th!["90%"],
+            th!["Median vs. first"],
+            th!["90% vs. first"],
+        ],
+    ];
+    for (index, curve) in pinned.iter().enumerate() {
+        let data = match curve.counted_in(measure) {
+            Some(data) => data,
+            None => {
+                table.add_child(tr![
+                    td![curve.label.clone()],
+                    td![attrs![At::ColSpan => 6], format!("Not counted in {}", unit)],
+                ]);
+                continue;
+            }
+        };
+        let (median, ninetieth) = key_percentiles(data);
+        let (median_difference, ninetieth_difference) = match first {
+            Some((first_median, first_ninetieth)) if index > 0 => (
+                difference(median, first_median),
+                difference(ninetieth, first_ninetieth),
+            ),
+            _ => ("-".to_string(), "-".to_string()),
+        };
+        table.add_child(tr![
+            td![curve.label.clone()],
+            td![data.iter().sum::<u32>().to_string()],
+            td![format!("{:.1}", stats::mean(data))],
+            td![median.to_string()],
+            td![ninetieth.to_string()],
+            td![median_difference],
+            td![ninetieth_difference],
+        ]);
+    }
+    table
+}
+
+/// Histogram of the results, with a setting for how many orbs each bar
+/// covers. Without a `bin_width`, one is picked to fit the data.
+fn histogram_section(
+    data: &Counter,
+    bin_width: Option<u32>,
+    log_scale: bool,
+    unit: &str,
+) -> Node<Msg> {
+    if data.is_empty() {
+        return seed::empty();
+    }
+    div![
+        histogram::histogram(
+            data,
+            bin_width.unwrap_or_else(|| histogram::auto_bin_width(data)),
+            log_scale,
+            unit,
+        ),
+        charts::download_button("histogram"),
+        div![
+            input![
+                id!["log_scale"],
+                simple_ev(Ev::Input, Msg::LogScaleToggle),
+                attrs![
+                    At::Type => "checkbox";
+                    At::Checked => log_scale.as_at_value();
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "log_scale";
+                ],
+                format!("Log scale for {}", unit),
+            ],
+        ],
+        div![
+            label![
+                attrs![
+                    At::For => "histogram_bin_width";
+                ],
+                format!("{} per bar: ", capitalized(unit)),
+            ],
+            input![
+                id!["histogram_bin_width"],
+                input_ev(Ev::Input, |text| Msg::HistogramBinWidthChange {
+                    width: text.parse::<u32>().ok().filter(|&width| width > 0),
+                }),
+                attrs![
+                    At::Type => "number";
+                    At::Class => "small_number";
+                    At::Min => 1;
+                    At::Placeholder => "auto";
+                    At::Value => bin_width.map(|width| width.to_string()).unwrap_or_default();
+                ],
+            ],
+        ],
+    ]
+}
+
+/// The word with its first letter in uppercase.
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Percentage for display, from a fraction in the range [0.0, 1.0], without
+/// trailing zeroes or rounding errors.
+pub fn percent_label(pct: f32) -> String {
+    ((pct * 10000.0).round() / 100.0).to_string()
+}
+
+/// Description of how the current settings compare to the saved ones.
+fn comparison_summary(comparison: &Comparison) -> Node<Msg> {
+    if comparison.runs < 2 {
+        return seed::empty();
+    }
+    let runs = comparison.runs as f64;
+    let mean = comparison.total_difference / runs;
+    let variance = (comparison.total_squared_difference - runs * mean * mean) / (runs - 1.0);
+    // Margin of error for a 95% confidence interval.
+    let margin = 1.96 * (variance.max(0.0) / runs).sqrt();
+    p![
+        id!["comparison"],
+        format!(
+            "Compared to the saved settings: {:+.1} orbs on average (±{:.1}). \
+             Took fewer orbs in {:.1}% of paired runs and more in {:.1}%.",
+            mean,
+            margin,
+            100.0 * comparison.fewer as f64 / runs,
+            100.0 * comparison.more as f64 / runs,
+        )
+    ]
+}
+
+/// The most goal parts to show in the table of when each was finished.
+const MAX_PART_ROWS: usize = 20;
+
+/// Table of when each of the goal's parts was finished and how often each was
+/// the last one, to show which ones take the longest.
+fn part_summary(
+    data: &Counter,
+    side_stats: &SideStats,
+    banner: &Banner,
+    goal: &CustomGoal,
+) -> Node<Msg> {
+    let parts = &goal.goals;
+    let total: u32 = data.iter().sum();
+    // Goals made of whole pools have too many parts for the table to help.
+    if side_stats.part_orbs.is_empty() || parts.len() > MAX_PART_ROWS || total == 0 {
+        return seed::empty();
+    }
+    let last_total: u32 = side_stats.last_parts.iter().sum();
+    let mut rows = vec![tr![
+        th!["Goal part"],
+        th!["Finished"],
+        th!["Finished last"],
+        th!["Average"],
+        th!["50%"],
+        th!["90%"],
+        th!["99%"],
+    ]];
+    for (index, (counter, part)) in side_stats.part_orbs.iter().zip(parts.iter()).enumerate() {
+        let finished: u32 = counter.iter().sum();
+        let name = match part.pool {
+            Pool::Focus => banner.focus_name(part.unit_color, goal.unit_index(index)),
+            _ => None,
+        };
+        let label = match name {
+            Some(name) => format!("{}. {} x {}", index + 1, part.num_copies, name),
+            None => format!(
+                "{}. {} x {} {}",
+                index + 1,
+                part.num_copies,
+                part.unit_color,
+                match part.pool {
+                    Pool::Focus => "focus",
+                    Pool::Fivestar => "off-focus 5*",
+                    Pool::FourstarFocus => "4* focus",
+                    Pool::FourstarSpecial => "4* special rate",
+                    Pool::Fourstar => "4*",
+                    Pool::Threestar => "3*",
+                }
+            ),
+        };
+        let mut row = tr![
+            td![label],
+            td![format!("{:.1}%", 100.0 * finished as f64 / total as f64)],
+            // Out of the runs that reached the goal.
+            td![if last_total > 0 {
+                let last = side_stats.last_parts.get(index).copied().unwrap_or(0);
+                format!("{:.1}%", 100.0 * last as f64 / last_total as f64)
+            } else {
+                "".to_string()
+            }],
+        ];
+        if finished > 0 {
+            row.add_child(td![format!("{:.1}", stats::mean(counter))]);
+            for orbs in stats::percentiles(counter, &[0.5, 0.9, 0.99]) {
+                row.add_child(td![orbs.to_string()]);
+            }
+        }
+        rows.push(row);
+    }
+    div![
+        id!["part_summary"],
+        p!["Orbs spent by the time each part of the goal was finished, in the runs that finished it:"],
+        table![rows],
+    ]
+}
+
+/// Breakdown of how many stones of the needed colors each summoning circle had.
+fn circle_summary(circles_by_target_stones: &[u64; 6]) -> Node<Msg> {
+    let total: u64 = circles_by_target_stones.iter().sum();
+    if total == 0 {
+        return seed::empty();
+    }
+    let target_stones: u64 = circles_by_target_stones
+        .iter()
+        .enumerate()
+        .map(|(stones, &count)| stones as u64 * count)
+        .sum();
+    let mut header = tr![th!["Stones of needed colors"]];
+    let mut frequencies = tr![th!["Share of circles"]];
+    for (stones, &count) in circles_by_target_stones.iter().enumerate() {
+        header.add_child(th![stones.to_string()]);
+        let share = 100.0 * count as f64 / total as f64;
+        frequencies.add_child(td![format!("{:.1}%", share)]);
+    }
+    div![
+        id!["circle_summary"],
+        p![format!(
+            "Summoning circles: {}, average stones of needed colors per circle: {:.2}",
+            total,
+            target_stones as f64 / total as f64
+        )],
+        table![header, frequencies],
+    ]
+}
+
+/// Summary of the distributions of things that happened along the way.
+fn side_stats_summary(
+    data: &Counter,
+    side_stats: &SideStats,
+    banner: &Banner,
+    strategy: &Strategy,
+) -> Node<Msg> {
+    let mut list = ul![id!["side_stats"]];
+    list.add_child(distribution_summary("Summons made", &side_stats.summons));
+    list.add_child(distribution_summary(
+        "Summoning circles opened",
+        &side_stats.circles,
+    ));
+    if banner.free_first_summon || banner.tickets > 0 || banner.daily_free_summon {
+        list.add_child(distribution_summary(
+            "Free summons used",
+            &side_stats.free_summons,
+        ));
+        let total = side_stats.runs(data);
+        list.add_child(li![format!(
+            "Goal reached using only free summons: {:.1}%",
+            100.0 * data[0] as f64 / total as f64
+        )]);
+    }
+    list.add_child(distribution_summary(
+        "Summons from circles with nothing to open",
+        &side_stats.forced_summons,
+    ));
+    list.add_child(distribution_summary(
+        "Orbs spent on those summons, which sniping would save if the game allowed skipping them",
+        &side_stats.forced_orbs,
+    ));
+    if banner.spark {
+        let total = side_stats.runs(data);
+        list.add_child(li![format!(
+            "Runs that needed a spark to reach the goal when they did: {:.1}%",
+            100.0 * side_stats.needed_spark_runs as f64 / total as f64
+        )]);
+        if strategy.stop_after_spark {
+            list.add_child(li![format!(
+                "Runs that stopped at the spark without reaching the goal: {:.1}%",
+                100.0 * side_stats.unfinished_runs as f64 / total as f64
+            )]);
+        }
+    }
+    if banner.starting_rates.1 > 0 {
+        list.add_child(distribution_summary(
+            "Off-focus 5* units obtained",
+            &side_stats.pitybreakers,
+        ));
+    }
+    list.add_child(distribution_summary(
+        "5* units summoned in total, focus and off-focus",
+        &side_stats.fivestars,
+    ));
+    if banner.fourstar_special {
+        list.add_child(distribution_summary(
+            "4* special rate units obtained",
+            &side_stats.fourstar_specials,
+        ));
+    }
+    list
+}
+
+/// How often the summons in the simulation gave 5* and 4* units, to compare
+/// against the summons made in the game.
+fn yield_summary(side_stats: &SideStats) -> Node<Msg> {
+    let runs: u32 = side_stats.summons.iter().sum();
+    let summons = stats::mean(&side_stats.summons) as f64 * runs as f64;
+    if summons == 0.0 {
+        return seed::empty();
+    }
+    let total = |counter: &Counter| stats::mean(counter) as f64 * runs as f64;
+    let focus = total(&side_stats.focus_units);
+    let off_focus = total(&side_stats.pitybreakers);
+    let fourstars = total(&side_stats.fourstars);
+    div![
+        id!["yield_summary"],
+        p!["Units from the summons made:"],
+        ul![
+            li![format!(
+                "5* units per 100 summons: {:.2} ({:.2} focus, {:.2} off-focus)",
+                100.0 * (focus + off_focus) / summons,
+                100.0 * focus / summons,
+                100.0 * off_focus / summons,
+            )],
+            li![format!(
+                "Off-focus 5* units per run: {:.2}",
+                off_focus / runs as f64
+            )],
+            li![format!(
+                "4* or better: {:.1}% of summons",
+                100.0 * (focus + off_focus + fourstars) / summons
+            )],
+        ],
+    ]
+}
+
+/// A single line describing a distribution with its mean and a few percentiles.
+fn distribution_summary(label: &str, data: &Counter) -> Node<Msg> {
+    let percentiles = stats::percentiles(data, &[0.5, 0.9, 0.99]);
+    li![format!(
+        "{}: average {:.2}, median {}, 90%: {}, 99%: {}",
+        label,
+        stats::mean(data),
+        percentiles[0],
+        percentiles[1],
+        percentiles[2],
+    )]
 }
@@ -0,0 +1,157 @@
+use seed::prelude::*;
+
+use fehsim_core::counter::Counter;
+use fehsim_core::stats;
+
+use super::charts::{self, Scale, HEIGHT};
+use super::Measure;
+use crate::Msg;
+
+/// Results that were pinned to compare other ones against on the chart.
+#[derive(Clone, Debug)]
+pub struct PinnedCurve {
+    /// What the results were for, to show in the legend.
+    pub label: String,
+    pub data: Counter,
+    /// The summons that each run took, if they're known.
+    pub summons: Counter,
+}
+
+impl PinnedCurve {
+    /// The pinned results, counted in the given measure, if they're known in
+    /// it.
+    pub fn counted_in(&self, measure: Measure) -> Option<&Counter> {
+        match measure {
+            Measure::Orbs => Some(&self.data),
+            Measure::Summons if !self.summons.is_empty() => Some(&self.summons),
+            Measure::Summons => None,
+        }
+    }
+}
+
+/// The fraction of runs that had reached the goal after spending each number
+/// of orbs, from 0 up to `max`.
+fn cumulative(data: &Counter, max: usize) -> Vec<f32> {
+    let total: u64 = data.iter().map(|&count| count as u64).sum();
+    let mut cumulative = Vec::with_capacity(max + 1);
+    let mut so_far = 0;
+    for &count in data.iter().take(max + 1) {
+        so_far += count as u64;
+        cumulative.push(so_far as f32 / total as f32);
+    }
+    cumulative.resize(max + 1, 1.0);
+    cumulative
+}
+
+/// Chance of having reached the goal after spending each number of orbs or
+/// summons, from 0 up to the most that any run took, along with any `pinned`
+/// results from earlier that are known in the same `measure`. If `hover` is
+/// given, as a fraction of the chart's width, shows the current chance at
+/// that point. If `orbs` is given, marks where that many orbs or summons is.
+/// With `log_scale`, they're spread out on a log scale. With `pinnable`, has a
+/// button for pinning the current results.
+pub fn cdf_chart(
+    data: &Counter,
+    pinned: &[PinnedCurve],
+    measure: Measure,
+    hover: Option<f32>,
+    orbs: Option<u32>,
+    log_scale: bool,
+    pinnable: bool,
+) -> Node<Msg> {
+    let max = match stats::max(data) {
+        Some(max) => pinned
+            .iter()
+            .filter_map(|curve| stats::max(curve.counted_in(measure)?))
+            .fold(max, u32::max)
+            .max(1) as usize,
+        None => return seed::empty(),
+    };
+    let scale = Scale::new(0, max as u32, log_scale);
+    let x = |orbs: usize| scale.x(orbs as f32);
+    let y = |chance: f32| HEIGHT - chance * HEIGHT;
+    // Steps, since the chance only changes at whole numbers of orbs.
+    let points = |cumulative: &[f32]| {
+        let mut points = vec![(x(0), y(cumulative[0]))];
+        for orbs in 1..cumulative.len() {
+            points.push((x(orbs), y(cumulative[orbs - 1])));
+            points.push((x(orbs), y(cumulative[orbs])));
+        }
+        points
+    };
+
+    let mut lines = g![];
+    for (index, curve) in pinned.iter().enumerate() {
+        if let Some(data) = curve.counted_in(measure) {
+            lines.add_child(charts::series_line(
+                index + 1,
+                &points(&cumulative(data, max)),
+            ));
+        }
+    }
+    let current = cumulative(data, max);
+    lines.add_child(charts::series_line(0, &points(&current)));
+
+    let readout = |orbs: usize| {
+        let shown = orbs.min(max);
+        charts::tooltip(
+            x(shown),
+            y(current[shown]),
+            format!(
+                "{} {}: {:.1}%",
+                orbs,
+                measure.unit(),
+                100.0 * current[shown]
+            ),
+        )
+    };
+    // The pointer takes over from the marked spot while it's on the chart.
+    let readout = match (hover, orbs) {
+        (Some(frac), _) => readout(scale.value_at(frac).round() as usize),
+        (None, Some(orbs)) => readout(orbs as usize),
+        (None, None) => seed::empty(),
+    };
+    let marker = match orbs {
+        Some(orbs) => charts::vertical_marker(x((orbs as usize).min(max))),
+        None => seed::empty(),
+    };
+    let mut legend = vec![];
+    if !pinned.is_empty() {
+        legend.push(charts::legend_entry(0, "Current results".into(), None));
+        for (index, curve) in pinned.iter().enumerate() {
+            legend.push(charts::legend_entry(
+                index + 1,
+                curve.label.clone(),
+                Some(Msg::CurveUnpin { index }),
+            ));
+        }
+    }
+    div![
+        svg![
+            id!["cdf_chart"],
+            class!["chart"],
+            charts::view_box(),
+            mouse_ev(Ev::MouseMove, |event| match charts::pointer_frac(&event) {
+                Some(frac) => Msg::CdfHover { frac: Some(frac) },
+                None => Msg::Null,
+            }),
+            mouse_ev(Ev::MouseLeave, |_| Msg::CdfHover { frac: None }),
+            marker,
+            lines,
+            readout,
+        ],
+        div![
+            id!["cdf_legend"],
+            legend,
+            if pinnable {
+                button![
+                    simple_ev(Ev::Click, Msg::CurvePin),
+                    "Pin these results to the chart",
+                ]
+            } else {
+                seed::empty()
+            },
+            charts::download_button("cdf_chart"),
+        ],
+    ]
+}
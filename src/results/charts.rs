@@ -0,0 +1,218 @@
+use seed::prelude::*;
+
+use std::fmt::Write;
+
+use wasm_bindgen::JsCast;
+
+use crate::Msg;
+
+/// The size of every chart, in SVG units. Charts scale to fit the page, so
+/// only the proportions matter.
+pub const WIDTH: f32 = 100.0;
+pub const HEIGHT: f32 = 60.0;
+/// Room left at the bottom of a chart for axis labels.
+pub const LABEL_HEIGHT: f32 = 6.0;
+
+/// Maps numbers of orbs to horizontal positions on a chart, either evenly or
+/// on a log scale, which spreads out the low end so that long tails don't
+/// squash everything else into a corner.
+#[derive(Copy, Clone, Debug)]
+pub struct Scale {
+    min: f32,
+    max: f32,
+    log: bool,
+}
+
+impl Scale {
+    /// A scale with `min` at the left edge of the chart and `max` at the right.
+    pub fn new(min: u32, max: u32, log: bool) -> Self {
+        Scale {
+            min: min as f32,
+            max: max.max(min + 1) as f32,
+            log,
+        }
+    }
+
+    fn transform(&self, value: f32) -> f32 {
+        if self.log {
+            // Shifted by one so that 0 orbs still has a place.
+            (value + 1.0).ln()
+        } else {
+            value
+        }
+    }
+
+    /// The horizontal position of a value, in chart coordinates.
+    pub fn x(&self, value: f32) -> f32 {
+        let (low, high) = (self.transform(self.min), self.transform(self.max));
+        (self.transform(value) - low) / (high - low) * WIDTH
+    }
+
+    /// The value at a position given as a fraction of the chart's width.
+    pub fn value_at(&self, frac: f32) -> f32 {
+        let (low, high) = (self.transform(self.min), self.transform(self.max));
+        let transformed = low + frac * (high - low);
+        if self.log {
+            transformed.exp() - 1.0
+        } else {
+            transformed
+        }
+    }
+}
+
+/// Attributes that give a chart's `svg` element the usual size.
+pub fn view_box() -> Attrs {
+    attrs![
+        At::ViewBox => format!("0 0 {} {}", WIDTH, HEIGHT);
+    ]
+}
+
+/// A line through the given points, in chart coordinates. Points in the
+/// middle of a flat stretch are skipped, since they don't change how the line
+/// looks.
+pub fn line(id: &str, points: &[(f32, f32)]) -> Node<Msg> {
+    path![
+        id![id],
+        class!["chart_line"],
+        attrs![
+            "d" => path_data(points);
+        ],
+    ]
+}
+
+/// The SVG path through the given points, leaving out the ones in the middle
+/// of a flat stretch.
+fn path_data(points: &[(f32, f32)]) -> String {
+    let mut path = String::new();
+    for (index, &(x, y)) in points.iter().enumerate() {
+        let flat = |other: Option<&(f32, f32)>| other.map_or(false, |&(_, other_y)| other_y == y);
+        if index == 0 {
+            write!(path, "M {} {} ", x, y).unwrap();
+        } else if !flat(points.get(index - 1)) || !flat(points.get(index + 1)) {
+            write!(path, "L {} {} ", x, y).unwrap();
+        }
+    }
+    path
+}
+
+/// The colors that tell apart the lines of a chart with more than one, in the
+/// order that they're used. The first one is for the current results.
+const SERIES_COLORS: [&str; 6] = [
+    "black",
+    "rgb(31, 119, 180)",
+    "rgb(255, 127, 14)",
+    "rgb(44, 160, 44)",
+    "rgb(148, 103, 189)",
+    "rgb(140, 86, 75)",
+];
+
+fn series_color(series: usize) -> &'static str {
+    SERIES_COLORS[series % SERIES_COLORS.len()]
+}
+
+/// A line of a chart with more than one, in the color of its `series`.
+pub fn series_line(series: usize, points: &[(f32, f32)]) -> Node<Msg> {
+    path![
+        class!["chart_line"],
+        style!["stroke" => series_color(series)],
+        attrs![
+            "d" => path_data(points);
+        ],
+    ]
+}
+
+/// An entry of a chart's legend, in the color of its `series`. With a
+/// `remove` message, has a button that sends it.
+pub fn legend_entry(series: usize, label: String, remove: Option<Msg>) -> Node<Msg> {
+    div![
+        class!["legend_entry"],
+        span![
+            class!["legend_swatch"],
+            style!["background-color" => series_color(series)],
+        ],
+        label,
+        match remove {
+            Some(msg) => button![class!["padleft"], simple_ev(Ev::Click, msg), "X"],
+            None => seed::empty(),
+        },
+    ]
+}
+
+/// A bar standing on `bottom`, in chart coordinates.
+pub fn bar(x: f32, width: f32, height: f32, bottom: f32) -> Node<Msg> {
+    rect![attrs![
+        "x" => x;
+        "y" => bottom - height;
+        At::Width => width;
+        At::Height => height;
+    ]]
+}
+
+/// A label along the bottom edge of the chart. `anchor` is the SVG
+/// text-anchor of the label at `x`.
+pub fn axis_label(x: f32, anchor: &str, label: String) -> Node<Msg> {
+    text![
+        attrs![
+            "x" => x;
+            "y" => HEIGHT - 1.0;
+            "font-size" => "10%";
+            "text-anchor" => anchor;
+        ],
+        label,
+    ]
+}
+
+/// A marked point with a label next to it, on whichever side of the point
+/// has more room.
+pub fn tooltip(x: f32, y: f32, label: String) -> Node<Msg> {
+    let on_left = x > WIDTH / 2.0;
+    g![
+        class!["chart_tooltip"],
+        circle![attrs![
+            "cx" => x;
+            "cy" => y;
+            "r" => "0.75px";
+        ]],
+        text![
+            attrs![
+                "font-size" => "15%";
+                "dx" => if on_left { x - 1.0 } else { x + 1.0 };
+                "dy" => y + 1.0;
+                "text-anchor" => if on_left { "end" } else { "begin" };
+                "dominant-baseline" => "hanging";
+            ],
+            label,
+        ],
+    ]
+}
+
+/// A dashed line from the top to the bottom of the chart, to mark a spot.
+pub fn vertical_marker(x: f32) -> Node<Msg> {
+    line_![
+        class!["chart_marker"],
+        attrs![
+            "x1" => x;
+            "x2" => x;
+            "y1" => 0;
+            "y2" => HEIGHT;
+        ],
+    ]
+}
+
+/// Button for saving the chart with the given id as an SVG file.
+pub fn download_button(chart: &'static str) -> Node<Msg> {
+    div![button![
+        simple_ev(Ev::Click, Msg::ChartDownload { chart }),
+        "Save chart as SVG",
+    ]]
+}
+
+/// Where a mouse event happened across the chart that's handling it, as a
+/// fraction of the chart's width.
+pub fn pointer_frac(event: &web_sys::MouseEvent) -> Option<f32> {
+    let target = event.current_target()?;
+    let target_el: &web_sys::Element = target.dyn_ref::<web_sys::SvgsvgElement>()?.as_ref();
+    let rect = target_el.get_bounding_client_rect();
+    let offset = event.client_x() as f64 - rect.left();
+    Some((offset / rect.width()).min(1.0).max(0.0) as f32)
+}
@@ -0,0 +1,73 @@
+use seed::prelude::*;
+
+use fehsim_core::counter::Counter;
+use fehsim_core::stats;
+
+use super::charts::{self, Scale, HEIGHT, LABEL_HEIGHT, WIDTH};
+use crate::Msg;
+
+/// How many bars to aim for when the bin width is picked automatically.
+const AUTO_BINS: u32 = 50;
+
+/// The bin width to use when none has been chosen, which splits the range of
+/// the data into about `AUTO_BINS` bars.
+pub fn auto_bin_width(data: &Counter) -> u32 {
+    match (stats::min(data), stats::max(data)) {
+        (Some(min), Some(max)) => ((max - min) / AUTO_BINS + 1).max(1),
+        _ => 1,
+    }
+}
+
+/// Bar chart of how many runs took each range of orbs or summons, `bin_width`
+/// wide, from the fewest to the most that any run took. With `log_scale`, the
+/// bars are spread out on a log scale.
+pub fn histogram(data: &Counter, bin_width: u32, log_scale: bool, unit: &str) -> Node<Msg> {
+    let (min, max) = match (stats::min(data), stats::max(data)) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return seed::empty(),
+    };
+    let bin_width = bin_width.max(1);
+    // Bins start at a multiple of the width, so that the edges are round
+    // numbers.
+    let first = min - min % bin_width;
+    let bins = (max - first) / bin_width + 1;
+    let counts = (0..bins)
+        .map(|bin| {
+            let start = first + bin * bin_width;
+            (start..start + bin_width)
+                .map(|value| data[value] as u64)
+                .sum::<u64>()
+        })
+        .collect::<Vec<_>>();
+    let tallest = *counts.iter().max().unwrap_or(&1) as f32;
+    let last = first + bins * bin_width - 1;
+    let scale = Scale::new(first, last + 1, log_scale);
+    let bar_area = HEIGHT - LABEL_HEIGHT;
+
+    let mut bars = g![id!["histogram_bars"]];
+    for (bin, &count) in counts.iter().enumerate() {
+        if count > 0 {
+            let start = (first + bin as u32 * bin_width) as f32;
+            let left = scale.x(start);
+            bars.add_child(charts::bar(
+                left,
+                scale.x(start + bin_width as f32) - left,
+                count as f32 / tallest * bar_area,
+                bar_area,
+            ));
+        }
+    }
+    svg![
+        id!["histogram"],
+        class!["chart"],
+        charts::view_box(),
+        bars,
+        charts::axis_label(1.0, "start", format!("{} {}", first, unit)),
+        charts::axis_label(
+            WIDTH / 2.0,
+            "middle",
+            format!("{} {}", scale.value_at(0.5).round(), unit),
+        ),
+        charts::axis_label(WIDTH - 1.0, "end", format!("{} {}", last, unit)),
+    ]
+}
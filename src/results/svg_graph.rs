@@ -1,22 +1,21 @@
 use seed::prelude::*;
 
-use std::fmt::Write;
+use fehsim_core::counter::Counter;
+use fehsim_core::stats;
 
-use wasm_bindgen::JsCast;
-
-use crate::counter::Counter;
-use crate::stats;
+use super::charts::{self, HEIGHT, WIDTH};
 use crate::Msg;
-const XMIN: f32 = 0.0;
-const YMIN: f32 = 0.0;
-const WIDTH: f32 = 100.0;
-const HEIGHT: f32 = 60.0;
 
 /// SVG elements for displaying the results within the graph. If `highlight` is
 /// given, places a label on the graph at the specified point. Otherwise, labels
-/// are placed at pre-set locations. Returns two elements, one for the line and
-/// one for the collection of labels.
-fn graph_line(data: &Counter, highlight: Option<f32>) -> (Node<Msg>, Node<Msg>) {
+/// are placed at the chosen percentiles. Returns two elements, one for the line
+/// and one for the collection of labels.
+fn graph_line(
+    data: &Counter,
+    highlight: Option<f32>,
+    percentiles: &[f32],
+    unit: &str,
+) -> (Node<Msg>, Node<Msg>) {
     // Sample every 0.1% in ranges 0%-10% and 90%-100%, and every 1% in between.
     // Probabilities only change sharply near the extremes, so this makes things
     // render more quickly without hurting smoothness.
@@ -28,39 +27,22 @@ fn graph_line(data: &Counter, highlight: Option<f32>) -> (Node<Msg>, Node<Msg>)
     let data_points = stats::percentiles(data, &sample_points);
 
     // Helper functions for converting between data values and graph coordinates.
-    let x = |pct: f32| pct as f32 * WIDTH + XMIN;
+    let x = |pct: f32| pct as f32 * WIDTH;
     let y = |val: f32| {
         let max = *data_points.last().unwrap() as f32;
         HEIGHT - (val / max) * HEIGHT
     };
 
-    let mut path = String::new();
-    if !data.is_empty() {
-        write!(
-            path,
-            "M {} {} ",
-            x(sample_points[0]),
-            y(data_points[0] as f32)
-        )
-        .unwrap();
-        for i in 1..data_points.len() {
-            if data_points[i] != data_points[i - 1] {
-                write!(
-                    path,
-                    "L {} {}",
-                    x(sample_points[i]),
-                    y(data_points[i] as f32)
-                )
-                .unwrap();
-            }
-        }
-    }
-    let path_el = path![
-        id!["graph_line"],
-        attrs![
-            "d" => path;
-        ],
-    ];
+    let points = if data.is_empty() {
+        vec![]
+    } else {
+        sample_points
+            .iter()
+            .zip(&data_points)
+            .map(|(&pct, &value)| (x(pct), y(value as f32)))
+            .collect::<Vec<_>>()
+    };
+    let path_el = charts::line("graph_line", &points);
     let mut points_el = g![id!["graph_highlights"],];
     let mut add_point = |pct: f32| {
         let value = stats::percentile(data, pct) as f32;
@@ -69,7 +51,7 @@ fn graph_line(data: &Counter, highlight: Option<f32>) -> (Node<Msg>, Node<Msg>)
             "cy" => y(value);
             "r" => "0.75px";
         ]]);
-        let label_text = format!("{}%: {} orbs", (pct * 1000.0).round() / 10.0, value);
+        let label_text = format!("{}%: {} {}", super::percent_label(pct), value, unit);
         points_el.add_child(text![
             attrs![
                 "font-size" => "15%";
@@ -111,7 +93,7 @@ fn graph_line(data: &Counter, highlight: Option<f32>) -> (Node<Msg>, Node<Msg>)
         if let Some(highlight) = highlight {
             add_point(highlight);
         } else {
-            for &pct in &[0.25, 0.5, 0.75, 0.9, 0.99] {
+            for &pct in percentiles {
                 add_point(pct);
             }
         }
@@ -120,31 +102,23 @@ fn graph_line(data: &Counter, highlight: Option<f32>) -> (Node<Msg>, Node<Msg>)
 }
 
 /// Graph for displaying the results. If `highlight` is given, places a label
-/// on the graph at the specified point. Otherwise, labels are placed at pre-set
-/// locations.
-pub fn graph(data: &Counter, highlight: Option<f32>) -> Node<Msg> {
-    let (path_el, points_el) = graph_line(data, highlight);
-    fn get_graph_width(event: &web_sys::Event) -> Option<f64> {
-        let target = event.target()?;
-        let target_el: &web_sys::Element = target.dyn_ref::<web_sys::SvgsvgElement>()?.as_ref();
-        let width = target_el.get_bounding_client_rect().width();
-        Some(width)
-    }
+/// on the graph at the specified point. Otherwise, labels are placed at the
+/// chosen percentiles.
+pub fn graph(data: &Counter, highlight: Option<f32>, percentiles: &[f32], unit: &str) -> Node<Msg> {
+    let (path_el, points_el) = graph_line(data, highlight, percentiles, unit);
     svg![
         id!["graph"],
+        class!["chart"],
         mouse_ev(Ev::Click, |click| {
-            if let Some(width) = get_graph_width(&click) {
-                let width_frac = (click.offset_x() as f32 / width as f32).min(0.999).max(0.0);
+            if let Some(width_frac) = charts::pointer_frac(&click) {
                 Msg::GraphHighlight {
-                    frac: (1000.0 * width_frac).round() / 1000.0,
+                    frac: (1000.0 * width_frac.min(0.999)).round() / 1000.0,
                 }
             } else {
                 Msg::Null
             }
         }),
-        attrs![
-            At::ViewBox => format!("{} {} {} {}", XMIN, YMIN, WIDTH, HEIGHT);
-        ],
+        charts::view_box(),
         path_el,
         if !data.is_empty() {
             text![
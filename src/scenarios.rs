@@ -0,0 +1,187 @@
+//! Saving the settings for later under a name, so that setups that come up
+//! again and again don't need to be entered again every time.
+
+use seed::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use fehsim_core::banner::Banner;
+use fehsim_core::counter::Counter;
+use fehsim_core::goal::Goal;
+use fehsim_core::migrate;
+use fehsim_core::strategy::Strategy;
+
+use crate::results::SideStats;
+use crate::{export, Msg};
+
+/// A named set of settings, optionally with the results that they had when
+/// they were saved.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// The version of the permalink format that the settings are saved in,
+    /// so that they can still be read after it changes.
+    pub version: u32,
+    /// The banner, goal, and strategy, saved the same way as in permalinks.
+    pub banner: String,
+    pub goal: String,
+    pub strategy: String,
+    #[serde(default)]
+    pub results: Option<SavedResults>,
+}
+
+/// Results saved along with a scenario.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedResults {
+    pub data: Counter,
+    pub side_stats: SideStats,
+    pub run_seed: Option<u64>,
+    pub converged: bool,
+}
+
+/// Saves a value in the same form as in permalinks.
+pub fn encode<T: Serialize>(value: &T) -> String {
+    base64::encode(&bincode::serialize(value).unwrap())
+}
+
+/// Reads a banner, goal, and strategy saved like in permalinks with the given
+/// version of the format, or `None` if that version can't be read anymore.
+pub fn decode_settings(
+    version: u32,
+    banner: &str,
+    goal: &str,
+    strategy: &str,
+) -> Option<(Banner, Goal, Strategy)> {
+    if version < migrate::OLDEST_VERSION || version > migrate::VERSION {
+        return None;
+    }
+    Some((
+        migrate::banner(banner, version)?,
+        migrate::goal(goal, version)?,
+        migrate::strategy(strategy, version)?,
+    ))
+}
+
+impl Scenario {
+    pub fn new(
+        name: String,
+        banner: &Banner,
+        goal: &Goal,
+        strategy: &Strategy,
+        results: Option<SavedResults>,
+    ) -> Self {
+        Scenario {
+            name,
+            version: migrate::VERSION,
+            banner: encode(banner),
+            goal: encode(goal),
+            strategy: encode(strategy),
+            results,
+        }
+    }
+
+    /// The banner, goal, and strategy that were saved, or `None` if they were
+    /// saved in a version of the format that can't be read anymore.
+    pub fn settings(&self) -> Option<(Banner, Goal, Strategy)> {
+        decode_settings(self.version, &self.banner, &self.goal, &self.strategy)
+    }
+}
+
+/// Section for saving the current settings as a scenario, and for loading
+/// and deleting the saved ones. `can_save_results` says whether there are
+/// current results that could be saved along with the settings.
+pub fn scenario_manager(
+    scenarios: &[Scenario],
+    name: &str,
+    with_results: bool,
+    can_save_results: bool,
+) -> Node<Msg> {
+    let mut list = ul![];
+    for (index, scenario) in scenarios.iter().enumerate() {
+        let readable = scenario.settings().is_some();
+        list.add_child(li![
+            button![
+                simple_ev(Ev::Click, Msg::ScenarioLoad { index }),
+                if !readable {
+                    attrs![
+                        At::Disabled => true;
+                        At::Title => "Saved with an older version of the simulator that can't be read anymore";
+                    ]
+                } else {
+                    attrs![]
+                },
+                "Load",
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::ScenarioDelete { index }),
+                "Delete",
+            ],
+            span![class!["padleft"], scenario.name.clone()],
+            if scenario.results.is_some() {
+                span![class!["padleft"], "(with results)"]
+            } else {
+                seed::empty()
+            },
+        ]);
+    }
+    details![
+        id!["scenarios"],
+        summary![format!("Saved scenarios ({})", scenarios.len())],
+        div![
+            label![
+                attrs![
+                    At::For => "scenario_name";
+                ],
+                "Name: ",
+            ],
+            input![
+                id!["scenario_name"],
+                input_ev(Ev::Input, |name| Msg::ScenarioNameChange { name }),
+                attrs![
+                    At::Value => name;
+                ],
+            ],
+            input![
+                id!["scenario_with_results"],
+                class!["padleft"],
+                simple_ev(Ev::Input, Msg::ScenarioWithResultsToggle),
+                attrs![
+                    At::Type => "checkbox";
+                    At::Checked => (with_results && can_save_results).as_at_value();
+                    At::Disabled => (!can_save_results).as_at_value();
+                ],
+            ],
+            label![
+                attrs![
+                    At::For => "scenario_with_results";
+                ],
+                "Include results",
+            ],
+            button![
+                class!["padleft"],
+                simple_ev(Ev::Click, Msg::ScenarioSave),
+                if name.trim().is_empty() {
+                    attrs![At::Disabled => true]
+                } else {
+                    attrs![]
+                },
+                "Save current settings",
+            ],
+        ],
+        list,
+        div![
+            button![
+                simple_ev(Ev::Click, Msg::BackupExport),
+                attrs![
+                    At::Title => "Save the settings, saved scenarios, percentages, and orb prices to a file";
+                ],
+                "Back up everything",
+            ],
+            export::file_picker(
+                "Restore from a backup",
+                "Replace everything with what was saved in a backup",
+                |file| Msg::BackupImport { file },
+            ),
+        ],
+    ]
+}
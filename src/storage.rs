@@ -0,0 +1,29 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Key for the percentiles shown in the results.
+pub const PERCENTILES_KEY: &str = "percentiles";
+
+/// Key for the prices of orb packs.
+pub const ORB_PRICES_KEY: &str = "orb_prices";
+
+/// Key for the saved scenarios.
+pub const SCENARIOS_KEY: &str = "scenarios";
+
+/// Key for the settings from the last visit.
+pub const SETTINGS_KEY: &str = "settings";
+
+/// Reads something saved in the browser's local storage, if it's there and
+/// still in the same format.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let storage = seed::storage::get_storage()?;
+    seed::storage::load_data(&storage, key)
+}
+
+/// Saves something to the browser's local storage. Browsers that don't allow
+/// it just don't remember anything.
+pub fn save<T: Serialize>(key: &str, data: &T) {
+    if let Some(storage) = seed::storage::get_storage() {
+        seed::storage::store_data(&storage, key, data);
+    }
+}
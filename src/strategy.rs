@@ -0,0 +1,133 @@
+use seed::prelude::*;
+
+use fehsim_core::strategy::{script, StoneChoice, Strategy};
+use fehsim_core::Color;
+
+use crate::Msg;
+
+/// Section for choosing the summoning strategy.
+pub fn strategy_selector(strategy: &Strategy) -> Node<Msg> {
+    let stones_option = |value: &str, label: &str, selected: bool| -> Node<Msg> {
+        let mut attrs = attrs![
+            At::Value => value;
+        ];
+        if selected {
+            attrs.add(At::Selected, "");
+        }
+        option![attrs, label]
+    };
+    let custom_colors = if let StoneChoice::Custom(colors) = &strategy.stones {
+        Some(*colors)
+    } else {
+        None
+    };
+    div![
+        id!["strategy_selector"],
+        label![
+            attrs![
+                At::For => "stone_choice";
+            ],
+            "Stones to open: ",
+        ],
+        select![
+            id!["stone_choice"],
+            input_ev("input", |text| {
+                let stones = match &*text {
+                    "snipe" => StoneChoice::Snipe,
+                    "all" => StoneChoice::OpenAll,
+                    "custom" => StoneChoice::Custom([false; 4]),
+                    "script" => StoneChoice::Script(String::new()),
+                    _ => return Msg::Null,
+                };
+                Msg::StrategyStonesChange { stones }
+            }),
+            stones_option(
+                "snipe",
+                "Only colors needed for the goal",
+                strategy.stones == StoneChoice::Snipe
+            ),
+            stones_option(
+                "all",
+                "Every stone",
+                strategy.stones == StoneChoice::OpenAll
+            ),
+            stones_option("custom", "Needed colors and...", custom_colors.is_some()),
+            stones_option(
+                "script",
+                "Script",
+                matches!(strategy.stones, StoneChoice::Script(_))
+            ),
+        ],
+        if let Some(colors) = custom_colors {
+            nodes![
+                extra_color_toggle(colors, Color::Red, "R"),
+                extra_color_toggle(colors, Color::Blue, "B"),
+                extra_color_toggle(colors, Color::Green, "G"),
+                extra_color_toggle(colors, Color::Colorless, "C"),
+            ]
+        } else {
+            vec![]
+        },
+        if let StoneChoice::Script(script) = &strategy.stones {
+            script_editor(script)
+        } else {
+            seed::empty()
+        },
+        input![
+            id!["stop_after_spark"],
+            class!["padleft"],
+            simple_ev(Ev::Input, Msg::StrategyStopAfterSparkToggle),
+            attrs![
+                At::Type => "checkbox";
+                At::Checked => strategy.stop_after_spark.as_at_value();
+            ],
+        ],
+        label![
+            attrs![
+                At::For => "stop_after_spark";
+            ],
+            "Stop after claiming a spark",
+        ],
+    ]
+}
+
+/// Text box for writing a strategy script, with any problems shown below it.
+fn script_editor(script: &str) -> Node<Msg> {
+    div![
+        textarea![
+            id!["strategy_script"],
+            input_ev("input", |script| Msg::StrategyScriptChange { script }),
+            attrs![
+                At::Rows => 3;
+                At::Placeholder => "open all when pity >= 5; snipe";
+                At::Value => script;
+            ],
+        ],
+        if let Err(message) = script::parse(script) {
+            p![class!["error"], message]
+        } else {
+            seed::empty()
+        },
+    ]
+}
+
+/// Checkbox for whether to always open stones of the given color.
+fn extra_color_toggle(colors: [bool; 4], color: Color, label: &str) -> Vec<Node<Msg>> {
+    let id = format!("extra_color_{}", color as usize);
+    nodes![
+        input![
+            simple_ev(Ev::Input, Msg::StrategyExtraColorToggle { color }),
+            attrs![
+                At::Id => &id;
+                At::Type => "checkbox";
+                At::Checked => colors[color as usize].as_at_value();
+            ],
+        ],
+        label![
+            attrs![
+                At::For => &id;
+            ],
+            label,
+        ],
+    ]
+}
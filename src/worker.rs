@@ -0,0 +1,341 @@
+//! Runs the simulation in a Web Worker so that the page stays responsive while
+//! it works. The worker loads the same wasm module as the page (see
+//! `worker.js`), and the two talk by posting bincode-serialized messages to
+//! each other. Browsers that can't start the worker run the simulation on the
+//! page instead, a short chunk at a time, and so do ones where the worker
+//! starts but fails to load.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use js_sys::{Date, Uint8Array};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, Worker};
+
+use fehsim_core::banner::Banner;
+use fehsim_core::counter::Counter;
+use fehsim_core::goal::Goal;
+use fehsim_core::sim::{self, exact, GachaSystem, PairedSim};
+use fehsim_core::strategy::Strategy;
+
+use crate::results::{Comparison, SideStats};
+
+/// How long the worker simulates for each request, in milliseconds.
+const WORKER_CHUNK_TIME: f64 = 100.0;
+
+/// How long to simulate at a time when running on the page, in milliseconds,
+/// before giving the browser a chance to do other things.
+pub const CHUNK_TIME: f64 = 30.0;
+
+/// A message from the page to the worker, asking it to simulate for a little
+/// while. The page keeps sending them until it has enough runs, so that it can
+/// stop at any time.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Request {
+    /// Start simulating new settings, throwing away the previous simulation.
+    Start {
+        run_id: u32,
+        banner: Banner,
+        goal: Goal,
+        strategy: Strategy,
+        /// Saved settings to simulate alongside the current ones, if any.
+        baseline: Option<(Banner, Goal, Strategy)>,
+        /// Banners to summon on before the current one, with the orbs for the
+        /// whole plan, if any.
+        plan: Option<(Vec<(Banner, Goal)>, u32)>,
+        seed: u64,
+        max_runs: u32,
+    },
+    /// Keep going with the current simulation.
+    Continue { run_id: u32, max_runs: u32 },
+    /// Calculate the results exactly, which can take a while, and respond
+    /// once with all of them.
+    Exact {
+        run_id: u32,
+        banner: Banner,
+        goal: Goal,
+        strategy: Strategy,
+    },
+}
+
+impl Request {
+    /// The ID of the results that the request is for.
+    pub fn run_id(&self) -> u32 {
+        match self {
+            Request::Start { run_id, .. }
+            | Request::Continue { run_id, .. }
+            | Request::Exact { run_id, .. } => *run_id,
+        }
+    }
+
+    /// The most runs to simulate for the request.
+    pub fn max_runs(&self) -> u32 {
+        match self {
+            Request::Start { max_runs, .. } | Request::Continue { max_runs, .. } => *max_runs,
+            Request::Exact { .. } => 0,
+        }
+    }
+}
+
+/// A message from the worker to the page, holding the runs finished for a
+/// request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Response {
+    /// Which request's simulation the runs came from, so that the page can
+    /// ignore runs of settings that have changed since.
+    pub run_id: u32,
+    pub data: Counter,
+    pub side_stats: SideStats,
+    pub comparison: Comparison,
+    /// Whether the data is the exact results instead of more runs.
+    pub exact: bool,
+}
+
+impl Response {
+    /// A response with no runs in it.
+    fn new(run_id: u32) -> Self {
+        Response {
+            run_id,
+            data: Counter::default(),
+            side_stats: SideStats::default(),
+            comparison: Comparison::default(),
+            exact: false,
+        }
+    }
+}
+
+/// Where the simulation runs.
+#[derive(Debug)]
+pub enum Runner {
+    Worker(SimWorker),
+    /// On the page itself, for browsers that can't start the worker.
+    Local(Simulator),
+}
+
+impl Runner {
+    /// Starts up a worker that calls `on_response` with each of its responses,
+    /// or falls back to running on the page if that doesn't work. If the
+    /// worker fails later on, such as when it can't load the simulation, it
+    /// calls `on_failure` instead, and the runner should be replaced with one
+    /// on the page.
+    pub fn new(on_response: impl Fn(Response) + 'static, on_failure: impl Fn() + 'static) -> Self {
+        match SimWorker::spawn(on_response, on_failure) {
+            Some(worker) => Runner::Worker(worker),
+            None => Runner::Local(Simulator::default()),
+        }
+    }
+
+    /// Sends a request to the worker, or gets ready to run it on the page.
+    pub fn send(&mut self, request: Request) {
+        match self {
+            Runner::Worker(worker) => worker.send(&request),
+            Runner::Local(simulator) => simulator.start(request),
+        }
+    }
+}
+
+/// The page's handle on the worker.
+pub struct SimWorker {
+    worker: Worker,
+    /// Kept around since the worker calls them for as long as it's alive.
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(JsValue)>,
+}
+
+impl SimWorker {
+    /// Starts up a worker that calls `on_response` with each of its responses,
+    /// if the browser allows it. The worker calls `on_failure` if its script
+    /// or the simulation can't be loaded, or if it stops with an error.
+    fn spawn(
+        on_response: impl Fn(Response) + 'static,
+        on_failure: impl Fn() + 'static,
+    ) -> Option<Self> {
+        let worker = Worker::new("/worker.js").ok()?;
+        let on_failure = Rc::new(on_failure);
+        let on_message = {
+            let on_failure = Rc::clone(&on_failure);
+            Closure::wrap(Box::new(move |event: MessageEvent| {
+                // Responses are always bytes, and the only other message is
+                // the one that says that the simulation couldn't be loaded.
+                if event.data().is_string() {
+                    on_failure();
+                    return;
+                }
+                let message = Uint8Array::new(&event.data()).to_vec();
+                if let Ok(response) = bincode::deserialize(&message) {
+                    on_response(response);
+                }
+            }) as Box<dyn FnMut(MessageEvent)>)
+        };
+        let on_error = Closure::wrap(Box::new(move |_: JsValue| {
+            on_failure();
+        }) as Box<dyn FnMut(JsValue)>);
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        Some(SimWorker {
+            worker,
+            _on_message: on_message,
+            _on_error: on_error,
+        })
+    }
+
+    /// Sends a request to the worker.
+    fn send(&self, request: &Request) {
+        let message = bincode::serialize(request).unwrap();
+        self.worker
+            .post_message(&Uint8Array::from(&message[..]))
+            .unwrap();
+    }
+}
+
+impl fmt::Debug for SimWorker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SimWorker").finish()
+    }
+}
+
+/// A simulation of the current settings.
+enum Simulation {
+    Single(Box<dyn GachaSystem>),
+    /// Runs of the saved settings alongside the current ones.
+    Paired(PairedSim),
+    /// An exact calculation of the given settings, waiting to be done.
+    Exact(Banner, Goal, Strategy),
+}
+
+/// Keeps the simulation that generated the current results, so that it can
+/// be continued.
+#[derive(Default)]
+pub struct Simulator {
+    /// The simulation, along with the ID of the results that it's for.
+    current: Option<(u32, Simulation)>,
+}
+
+impl Simulator {
+    /// Gets ready to run a request. Requests to continue keep the current
+    /// simulation, and `run_for` checks that it's the right one.
+    fn start(&mut self, request: Request) {
+        if let Request::Exact {
+            run_id,
+            banner,
+            goal,
+            strategy,
+        } = request
+        {
+            self.current = Some((run_id, Simulation::Exact(banner, goal, strategy)));
+        } else if let Request::Start {
+            run_id,
+            banner,
+            goal,
+            strategy,
+            baseline,
+            plan,
+            seed,
+            ..
+        } = request
+        {
+            let current = match plan {
+                Some((mut steps, budget)) => {
+                    steps.push((banner, goal));
+                    sim::new_plan_system(steps, strategy, budget, seed)
+                }
+                None => sim::new_system(banner, goal, strategy, seed),
+            };
+            let simulation = match baseline {
+                Some((banner, goal, strategy)) => {
+                    let baseline = sim::new_system(banner, goal, strategy, seed);
+                    Simulation::Paired(PairedSim::new(baseline, current, seed))
+                }
+                None => Simulation::Single(current),
+            };
+            self.current = Some((run_id, simulation));
+        }
+    }
+
+    /// Simulates for about `time` milliseconds or until `max_runs` runs are
+    /// finished, whichever comes first, and returns the finished runs. Exact
+    /// calculations are done all at once instead.
+    pub fn run_for(&mut self, run_id: u32, time: f64, max_runs: u32) -> Response {
+        let mut response = Response::new(run_id);
+        if let Some((current_id, Simulation::Exact(banner, goal, strategy))) = &self.current {
+            if *current_id == run_id {
+                response.data = exact::distribution(banner, goal, strategy).unwrap_or_default();
+                response.exact = true;
+            }
+            self.current = None;
+            return response;
+        }
+        let simulation = match &mut self.current {
+            Some((current_id, simulation)) if *current_id == run_id => simulation,
+            _ => return response,
+        };
+
+        // Time per simulation varies wildly depending on device performance
+        // and sim parameters, so it starts with a very low number of runs
+        // between clock checks and goes up from there.
+        let mut limit = 10;
+        let mut runs = 0;
+        let start = Date::now();
+        while runs < max_runs && Date::now() - start < time {
+            let batch = limit.min(max_runs - runs);
+            for _ in 0..batch {
+                let result = match simulation {
+                    Simulation::Single(sim) => sim.roll_until_goal(),
+                    Simulation::Paired(paired_sim) => {
+                        let (baseline, result) = paired_sim.roll_until_goal();
+                        response.comparison.record(&baseline, &result);
+                        result
+                    }
+                    Simulation::Exact(..) => unreachable!(),
+                };
+                if result.reached_goal {
+                    response.data[result.orbs] += 1;
+                }
+                response.side_stats.record(&result);
+            }
+            runs += batch;
+            limit *= 2;
+        }
+        response
+    }
+}
+
+impl fmt::Debug for Simulator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Simulator").finish()
+    }
+}
+
+thread_local! {
+    /// The worker's simulation.
+    static SIMULATOR: RefCell<Simulator> = RefCell::new(Simulator::default());
+}
+
+/// Entry point for messages posted to the worker.
+#[wasm_bindgen]
+pub fn worker_message(message: Vec<u8>) {
+    let request: Request = match bincode::deserialize(&message) {
+        Ok(request) => request,
+        Err(_) => return,
+    };
+    let run_id = request.run_id();
+    let max_runs = request.max_runs();
+    let response = SIMULATOR.with(|simulator| {
+        let mut simulator = simulator.borrow_mut();
+        simulator.start(request);
+        simulator.run_for(run_id, WORKER_CHUNK_TIME, max_runs)
+    });
+    post(&response);
+}
+
+/// Sends a response from the worker to the page.
+fn post(response: &Response) {
+    let message = bincode::serialize(response).unwrap();
+    js_sys::global()
+        .unchecked_into::<DedicatedWorkerGlobalScope>()
+        .post_message(&Uint8Array::from(&message[..]))
+        .unwrap();
+}
@@ -0,0 +1,18 @@
+// Runs the simulation off of the page's thread. See src/worker.rs.
+importScripts('/pkg/feh_sim_seed.js');
+
+// Holds on to requests that arrive while the wasm module is still loading.
+let pending = [];
+self.onmessage = event => pending.push(event.data);
+
+wasm_bindgen('/pkg/feh_sim_seed_bg.wasm')
+    .then(() => {
+        self.onmessage = event => wasm_bindgen.worker_message(event.data);
+        pending.forEach(data => wasm_bindgen.worker_message(data));
+        pending = [];
+    })
+    .catch(error => {
+        console.error(error);
+        // Lets the page know to simulate on its own instead.
+        self.postMessage('failed');
+    });